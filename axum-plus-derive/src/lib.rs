@@ -0,0 +1,193 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, DeriveInput, Ident, LitInt, LitStr};
+
+/// Derives `axum_plus::BodyError` with a generated `<Name>Error` struct holding the
+/// rejection message and a map of field -> messages.
+///
+/// `#[body_error(json_status = 400, validate_status = 422)]` overrides the rejection statuses,
+/// `#[body_error(status = 422)]` sets both. `#[body_error(schema_key = "_schema")]` overrides
+/// the key struct level validation errors are reported under.
+#[proc_macro_derive(BodyError, attributes(body_error))]
+pub fn derive_body_error(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut json_status = None;
+    let mut validate_status = None;
+    let mut schema_key = None;
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("body_error") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("schema_key") {
+                schema_key = Some(meta.value()?.parse::<LitStr>()?);
+                return Ok(());
+            }
+
+            let target = if meta.path.is_ident("status") {
+                None
+            } else if meta.path.is_ident("json_status") {
+                Some(&mut json_status)
+            } else if meta.path.is_ident("validate_status") {
+                Some(&mut validate_status)
+            } else {
+                return Err(meta.error(
+                    "unsupported body_error attribute, expected `status`, `json_status`, `validate_status` or `schema_key`",
+                ));
+            };
+
+            let lit: LitInt = meta.value()?.parse()?;
+            let code: u16 = lit.base10_parse()?;
+
+            if !(100..=999).contains(&code) {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "status must be within 100..=999",
+                ));
+            }
+
+            match target {
+                Some(target) => *target = Some(code),
+                None => {
+                    json_status = Some(code);
+                    validate_status = Some(code);
+                }
+            }
+
+            Ok(())
+        })?;
+    }
+
+    let vis = &input.vis;
+    let ident = &input.ident;
+    let error = format_ident!("{}Error", ident);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let json_status = json_status.map(|code| {
+        quote! {
+            json_status: ::axum_plus::__private::StatusCode::from_u16(#code).unwrap(),
+        }
+    });
+
+    let validate_status = validate_status.map(|code| {
+        quote! {
+            validate_status: ::axum_plus::__private::StatusCode::from_u16(#code).unwrap(),
+        }
+    });
+
+    let schema_key = schema_key.map(|key| quote! { schema_key: #key, });
+
+    let config =
+        (json_status.is_some() || validate_status.is_some() || schema_key.is_some()).then(|| {
+            quote! {
+                fn config() -> ::axum_plus::BodyConfig {
+                    ::axum_plus::BodyConfig {
+                        #json_status
+                        #validate_status
+                        #schema_key
+                        ..::std::default::Default::default()
+                    }
+                }
+            }
+        });
+
+    Ok(quote! {
+        #[derive(Debug, ::axum_plus::__private::serde::Serialize)]
+        #[serde(crate = "::axum_plus::__private::serde")]
+        #vis struct #error {
+            pub message: ::std::string::String,
+
+            #[serde(skip_serializing_if = "::std::collections::HashMap::is_empty")]
+            pub fields: ::std::collections::HashMap<
+                ::std::string::String,
+                ::std::vec::Vec<::std::string::String>,
+            >,
+        }
+
+        impl #impl_generics ::axum_plus::BodyError for #ident #ty_generics #where_clause {
+            type Error = #error;
+
+            fn json_error(rejection: ::axum_plus::__private::JsonRejection) -> Self::Error {
+                #error {
+                    message: rejection.body_text(),
+                    fields: ::axum_plus::__private::json_messages(&rejection),
+                }
+            }
+
+            fn json_path_error(
+                path: ::std::string::String,
+                rejection: ::axum_plus::__private::JsonRejection,
+            ) -> Self::Error {
+                let message = rejection.body_text();
+
+                #error {
+                    fields: ::std::collections::HashMap::from([(
+                        path,
+                        ::std::vec![message.clone()],
+                    )]),
+                    message,
+                }
+            }
+
+            fn validate_error(err: ::axum_plus::__private::ValidationErrors) -> Self::Error {
+                #error {
+                    message: ::std::string::String::from("Invalid payload data!"),
+                    fields: ::axum_plus::__private::field_messages(
+                        &err,
+                        <Self as ::axum_plus::BodyError>::config().schema_key,
+                    ),
+                }
+            }
+
+            #config
+        }
+    })
+}
+
+/// Derives `axum_plus::ResponseStatus`, `Respond<T>` sends `OK` unless
+/// `#[response_status(CREATED)]` names another status constant of `axum_plus`.
+#[proc_macro_derive(ResponseStatus, attributes(response_status))]
+pub fn derive_response_status(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand_response_status(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand_response_status(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let mut status = None;
+
+    for attr in &input.attrs {
+        if !attr.path().is_ident("response_status") {
+            continue;
+        }
+
+        status = Some(attr.parse_args::<Ident>()?);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let status = status.map(|status| {
+        quote! {
+            const STATUS: ::axum_plus::__private::StatusCode = ::axum_plus::#status;
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::axum_plus::ResponseStatus for #ident #ty_generics #where_clause {
+            #status
+        }
+    })
+}
@@ -0,0 +1,100 @@
+//! Throughput of `Body<T>` extraction next to `Json<T>` and `validate`, which buffers and
+//! parses the body the way a default `Body<T>` does. Compare the pooled and non-pooled readers
+//! or the simd-json and serde_json parsers against a default build with:
+//!
+//! ```sh
+//! cargo bench --bench body -- --save-baseline default
+//! cargo bench --bench body --features buffer-pool -- --baseline default
+//! cargo bench --bench body --features simd -- --baseline default
+//! ```
+//!
+//! With `buffer-pool` the `body` and `json` results of one run are already side by side.
+
+use axum::{
+    extract::{FromRequest, Request},
+    http::header::CONTENT_TYPE,
+    Json,
+};
+use axum_plus::{Body, DefaultError};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Validate)]
+struct Order {
+    #[validate(length(min = 1))]
+    customer: String,
+
+    #[validate(length(min = 1))]
+    items: Vec<Item>,
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+struct Item {
+    sku: String,
+
+    #[validate(range(min = 1))]
+    quantity: u32,
+}
+
+impl DefaultError for Order {}
+
+fn payload() -> Vec<u8> {
+    let items = (0..64)
+        .map(|index| Item {
+            sku: format!("SKU-{index:05}"),
+            quantity: index + 1,
+        })
+        .collect();
+
+    let order = Order {
+        customer: "West".into(),
+        items,
+    };
+
+    serde_json::to_vec(&order).unwrap()
+}
+
+fn request(payload: &[u8]) -> Request {
+    Request::post("/")
+        .header(CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(payload.to_vec()))
+        .unwrap()
+}
+
+fn body(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let payload = payload();
+
+    let mut group = c.benchmark_group("body");
+    group.throughput(Throughput::Bytes(payload.len() as u64));
+    group.bench_function("body", |b| {
+        b.to_async(&runtime).iter_batched(
+            || request(&payload),
+            |req| async {
+                let Ok(Body(order)) = Body::<Order>::from_request(req, &()).await else {
+                    panic!("valid order was rejected");
+                };
+                order
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("json", |b| {
+        b.to_async(&runtime).iter_batched(
+            || request(&payload),
+            |req| async {
+                let Ok(Json(order)) = Json::<Order>::from_request(req, &()).await else {
+                    panic!("valid order was rejected");
+                };
+                order.validate().expect("valid order failed validation");
+                order
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, body);
+criterion_main!(benches);
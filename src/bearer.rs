@@ -0,0 +1,74 @@
+//! `Bearer<T>` decoding and validating JWT claims.
+
+use std::{any::type_name, marker::PhantomData};
+
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use std_plus::{f, new, string};
+use validator::Validate;
+
+use crate::{fail_fast_errors, parse_error, BodyError, Static, INTERNAL_SERVER_ERROR};
+
+/// Claims of the JWT sent as `Authorization: Bearer <token>` (the scheme is case-insensitive),
+/// decoded with the `BearerKey<T>` added with `StaticLayer` and validated. A missing or invalid
+/// token and claims failing validation are all rejected with `BodyConfig::token_status`.
+pub struct Bearer<T>(pub T);
+
+/// Verification key and rules (algorithms, `exp`, issuer, ...) of `Bearer<T>`.
+#[derive(new)]
+pub struct BearerKey<T> {
+    pub key: jsonwebtoken::DecodingKey,
+    pub validation: jsonwebtoken::Validation,
+    #[new(default)]
+    _marker: PhantomData<fn() -> T>,
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Bearer<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + 'static + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let Some(Static(key)) = parts.extensions.get::<Static<BearerKey<T>>>().copied() else {
+            tracing::error!(
+                "Failed to  extract {}, is it added via StaticLayer",
+                type_name::<BearerKey<T>>()
+            );
+
+            let error = parse_error("authorization", string!("Unknown error occurred!"));
+            return Err((INTERNAL_SERVER_ERROR, Json(T::validate_error(error))));
+        };
+
+        let unauthorized =
+            |message: String| (T::config().token_status, Json(T::token_error(message)));
+
+        let token = parts
+            .headers
+            .get(axum::http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim_start().split_once(' '))
+            .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+            .map(|(_, token)| token.trim())
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| unauthorized(string!("Missing bearer token")))?;
+
+        let claims = jsonwebtoken::decode::<T>(token, &key.key, &key.validation)
+            .map_err(|err| unauthorized(f!("Invalid bearer token: {}", err)))?
+            .claims;
+
+        // Claims failing validation are an invalid token too, not an unprocessable payload
+        if let Err(err) = claims.validate() {
+            let error = T::validate_error(fail_fast_errors::<T>(err));
+            return Err((T::config().token_status, Json(error)));
+        };
+
+        Ok(Bearer(claims))
+    }
+}
@@ -0,0 +1,1494 @@
+//! `Body<T>` and the JSON body extractors built on it.
+
+use std::{
+    any::type_name,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use axum::{
+    body::Bytes,
+    extract::{
+        rejection::{BytesRejection, JsonRejection, MissingJsonContentType},
+        FromRequest, Request,
+    },
+    http::{
+        header::{HeaderName, CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use http_body_util::Limited;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std_plus::{f, new, string};
+use validator::{Validate, ValidateArgs, ValidationErrors};
+
+use crate::{
+    fail_fast_errors, flatten_errors_with, json_rejection_status, parse_error,
+    validation_rejection, BodyError, INTERNAL_SERVER_ERROR, PAYLOAD_TOO_LARGE,
+};
+
+#[cfg(feature = "compression")]
+use axum::http::header::CONTENT_ENCODING;
+#[cfg(feature = "buffer-pool")]
+use axum::RequestExt;
+#[cfg(feature = "buffer-pool")]
+use futures_util::StreamExt;
+#[cfg(any(feature = "borrow", feature = "jsonschema", feature = "utoipa"))]
+use std::marker::PhantomData;
+#[cfg(feature = "timeout")]
+use std::time::Duration;
+
+#[cfg(feature = "trace")]
+use crate::flatten_errors;
+#[cfg(feature = "jsonschema")]
+use crate::Static;
+#[cfg(feature = "buffer-pool")]
+use crate::BAD_REQUEST;
+#[cfg(feature = "timeout")]
+use crate::REQUEST_TIMEOUT;
+#[cfg(feature = "compression")]
+use crate::UNSUPPORTED_MEDIA_TYPE;
+
+/// JSON body extractor validating `T` after deserializing it.
+///
+/// `T` must implement `BodyError`, which decides the payload of both the JSON and the
+/// validation rejections. `impl DefaultError for T {}` opts into the default
+/// `{ message, fields }` payload.
+///
+/// `GET`, `HEAD` and `OPTIONS` requests with an empty body skip the `Content-Type` check and
+/// are parsed as `BodyConfig::empty_body` says, `{}` by default, so `T` with only optional
+/// fields is extracted and any other `T` is rejected through `BodyError::json_error`.
+/// `EmptyBody::Reject` still wins and rejects them. Other methods always need a JSON
+/// `Content-Type`, with it an empty body falls back to `empty_body` the same way.
+///
+/// `application/json` and `application/*+json` (e.g. `application/vnd.api+json`) are accepted
+/// whatever their parameters, such as `; charset=utf-8`, the body must be UTF-8 regardless.
+/// Other types are rejected with the `MissingJsonContentType` rejection of axum, sent with
+/// `BodyConfig::content_type_status` (`UNSUPPORTED_MEDIA_TYPE`).
+///
+/// Map fields keep the order of the payload when typed as `indexmap::IndexMap` (with its
+/// `serde` feature), `Body<T>` never re-serializes the payload.
+#[derive(Deserialize)]
+pub struct Body<T>(pub T);
+
+/// Zero-copy `Body<T>` for types borrowing from the payload (e.g. `&'a str` fields), the
+/// buffered body is kept alive alongside the value. `T` derives `yoke::Yokeable` and is named
+/// with a `'static` lifetime, e.g. `BodyRef<Order<'static>>`, read it back with `get()`. The
+/// payload goes through the same checks and `BodyError` hooks as `Body<T>`.
+#[cfg(feature = "borrow")]
+pub struct BodyRef<T: for<'a> yoke::Yokeable<'a>>(pub yoke::Yoke<T, Vec<u8>>);
+
+#[cfg(feature = "borrow")]
+impl<T: for<'a> yoke::Yokeable<'a>> BodyRef<T> {
+    pub fn get<'a>(&'a self) -> &'a <T as yoke::Yokeable<'a>>::Output {
+        self.0.get()
+    }
+}
+
+/// JSON array where every element is validated, rejects with the errors of all the invalid
+/// elements keyed by index, e.g. `{ "0": {...}, "3": {...} }`.
+pub struct BodyVec<T>(pub Vec<T>);
+
+/// JSON body validated with `ValidateArgs`, using the `ValidateContext::Context` found in
+/// the request extensions (e.g. inserted by `axum::Extension` as a layer).
+pub struct BodyContext<T>(pub T);
+
+/// JSON body validated with `ValidateArgs` against the router state.
+///
+/// ```
+/// use std::{collections::HashSet, sync::Arc};
+///
+/// use axum::{routing::post, Router};
+/// use axum_plus::{DefaultError, StateValidate};
+/// use serde::Deserialize;
+/// use validator::{Validate, ValidationError};
+///
+/// #[derive(Clone)]
+/// struct AppState {
+///     coupons: Arc<HashSet<String>>,
+/// }
+///
+/// fn known_coupon(code: &String, state: &AppState) -> Result<(), ValidationError> {
+///     match state.coupons.contains(code) {
+///         true => Ok(()),
+///         false => Err(ValidationError::new("unknown_coupon")),
+///     }
+/// }
+///
+/// #[derive(Deserialize, Validate)]
+/// #[validate(context = AppState)]
+/// struct Redeem {
+///     #[validate(custom(function = "known_coupon", use_context))]
+///     coupon: String,
+/// }
+///
+/// impl DefaultError for Redeem {}
+///
+/// async fn redeem(StateValidate(redeem): StateValidate<Redeem>) -> String {
+///     redeem.coupon
+/// }
+///
+/// let state = AppState {
+///     coupons: Arc::new(HashSet::from([String::from("WEST10")])),
+/// };
+///
+/// let app: Router = Router::new()
+///     .route("/redeem", post(redeem))
+///     .with_state(state);
+/// ```
+pub struct StateValidate<T>(pub T);
+
+pub trait ValidateContext {
+    type Context: Clone + Send + Sync + 'static;
+}
+
+/// `Body<T>` whose validation messages are rewritten by the `Resolver` found in the request
+/// extensions, falls back to the messages of `validator` when none is present.
+pub struct LocalizedBody<T>(pub T);
+
+pub trait MessageResolver: Send + Sync {
+    fn message(
+        &self,
+        code: &str,
+        params: &HashMap<Cow<'static, str>, serde_json::Value>,
+    ) -> Option<String>;
+}
+
+#[derive(Clone)]
+pub struct Resolver(pub Arc<dyn MessageResolver>);
+
+/// Request extension set by a layer on trusted routes, `SkipValidation(true)` makes `Body<T>`
+/// deserialize without calling `validate()`. Without it `Body<T>` always validates.
+#[derive(new, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SkipValidation(pub bool);
+
+/// Request extension bounding how long `Body<T>` may spend validating, overrides
+/// `BodyConfig::validate_timeout` (with the `timeout` feature).
+#[cfg(feature = "timeout")]
+#[derive(new, Clone, Copy, Debug)]
+pub struct ValidationTimeout(pub Duration);
+
+/// JSON body checked against the `JsonSchema<T>` added with `StaticLayer` before it's
+/// deserialized, instead of a `Validate` impl. Violations go to `BodyError::schema_error`.
+#[cfg(feature = "jsonschema")]
+pub struct SchemaBody<T>(pub T);
+
+/// Compiled JSON Schema used by `SchemaBody<T>`, usually kept in a `LazyLock` static.
+#[cfg(feature = "jsonschema")]
+pub struct JsonSchema<T> {
+    validator: jsonschema::Validator,
+    _marker: PhantomData<fn() -> T>,
+}
+
+pub(crate) fn content_length(headers: &HeaderMap) -> Option<usize> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Same rule as `axum::Json`, `application/json` or any `application/*+json`.
+pub(crate) fn json_content_type(headers: &HeaderMap) -> bool {
+    let Some(mime) = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+    else {
+        return false;
+    };
+
+    let mime = mime.trim().to_ascii_lowercase();
+    match mime.split_once('/') {
+        Some(("application", subtype)) => subtype == "json" || subtype.ends_with("+json"),
+        _ => false,
+    }
+}
+
+pub(crate) fn has_content_type(headers: &HeaderMap, expected: &str) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(expected))
+}
+
+/// Walks any JSON value failing on the first object with a repeated key, serde_json keeps
+/// the last one silently.
+struct UniqueKeys;
+
+impl<'de> Deserialize<'de> for UniqueKeys {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(UniqueKeys)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for UniqueKeys {
+    type Value = UniqueKeys;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("any JSON value")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = std::collections::HashSet::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            if keys.contains(&key) {
+                return Err(serde::de::Error::custom(f!("duplicate key `{}`", key)));
+            }
+
+            map.next_value::<UniqueKeys>()?;
+            keys.insert(key);
+        }
+
+        Ok(UniqueKeys)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        while seq.next_element::<UniqueKeys>()?.is_some() {}
+        Ok(UniqueKeys)
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Self::Value, E> {
+        Ok(UniqueKeys)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Self::Value, E> {
+        Ok(UniqueKeys)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Self::Value, E> {
+        Ok(UniqueKeys)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Self::Value, E> {
+        Ok(UniqueKeys)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Self::Value, E> {
+        Ok(UniqueKeys)
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(UniqueKeys)
+    }
+}
+
+/// Rejects bodies declared bigger than `BodyConfig::max_bytes` and enforces it while buffering.
+pub(crate) fn limit_body<E: BodyError>(
+    req: Request,
+) -> Result<Request, (StatusCode, Json<E::Error>)> {
+    let Some(limit) = E::config().max_bytes else {
+        return Ok(req);
+    };
+
+    if content_length(req.headers()).is_some_and(|length| length > limit) {
+        return Err((PAYLOAD_TOO_LARGE, Json(E::limit_error(limit))));
+    }
+
+    // The declared length can lie, so enforce the limit while buffering too
+    Ok(req.map(|body| axum::body::Body::new(Limited::new(body, limit))))
+}
+
+#[cfg_attr(
+    all(feature = "buffer-pool", not(feature = "borrow")),
+    allow(dead_code)
+)]
+
+fn bytes_rejection<E: BodyError>(rejection: BytesRejection) -> (StatusCode, Json<E::Error>) {
+    match E::config().max_bytes {
+        Some(limit) if rejection.status() == PAYLOAD_TOO_LARGE => {
+            (PAYLOAD_TOO_LARGE, Json(E::limit_error(limit)))
+        }
+        // Failing to buffer keeps its own status, e.g. a `DefaultBodyLimit` 413
+        _ => (rejection.status(), Json(E::bytes_error(rejection))),
+    }
+}
+
+/// Bound on decompressed bodies when `BodyConfig::max_bytes` isn't set, same as axum's
+/// default body limit.
+#[cfg(feature = "compression")]
+const MAX_DECOMPRESSED_BYTES: usize = 2 * 1024 * 1024;
+
+/// Decodes a `Content-Encoding` of gzip, deflate or br, `None` for identity bodies. The output
+/// is bounded by the body limit so a small compressed body can't expand without limit.
+#[cfg(feature = "compression")]
+fn decompress<E: BodyError>(
+    encoding: Option<&str>,
+    bytes: &[u8],
+) -> Result<Option<Vec<u8>>, (StatusCode, Json<E::Error>)> {
+    use std::io::Read;
+
+    let reader: Box<dyn Read + '_> = match encoding {
+        None | Some("identity") => return Ok(None),
+        _ if bytes.is_empty() => return Ok(None),
+        Some("gzip" | "x-gzip") => Box::new(flate2::read::GzDecoder::new(bytes)),
+        Some("deflate") => Box::new(flate2::read::ZlibDecoder::new(bytes)),
+        Some("br") => Box::new(brotli::Decompressor::new(bytes, 4096)),
+        Some(encoding) => {
+            let message = f!("Unsupported content encoding `{}`", encoding);
+            let error = E::validate_error(parse_error("content-encoding", message));
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)));
+        }
+    };
+
+    let limit = E::config().max_bytes.unwrap_or(MAX_DECOMPRESSED_BYTES);
+    let mut decoded = Vec::new();
+
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|err| {
+            let message = f!("Failed to decompress the body: {}", err);
+            (
+                E::config().json_status,
+                Json(E::validate_error(parse_error("body", message))),
+            )
+        })?;
+
+    if decoded.len() > limit {
+        return Err((PAYLOAD_TOO_LARGE, Json(E::limit_error(limit))));
+    }
+
+    Ok(Some(decoded))
+}
+
+/// Why `Body<T>` rejected a request, `Result<Body<T>, BodyRejection<T>>` lets a handler match
+/// on the cause. Responds like the plain `Body<T>` rejection through the `BodyError` hooks of
+/// `T`, `into_parts` gives that `(StatusCode, Json<T::Error>)` back.
+pub enum BodyRejection<T: BodyError> {
+    /// The body isn't JSON or doesn't match `T`. `body` is the buffered payload for syntax and
+    /// data errors, `path` the failing field with the `path-errors` feature.
+    Json {
+        rejection: JsonRejection,
+        body: Option<Bytes>,
+        path: Option<String>,
+    },
+    /// `T` was parsed but failed validation.
+    Validation(ValidationErrors),
+    /// Any other failure (size limit, encoding, charset, captured headers), already built
+    /// through the `BodyError` hooks.
+    Error(StatusCode, T::Error),
+}
+
+impl<T: BodyError> BodyRejection<T> {
+    fn json(rejection: JsonRejection) -> Self {
+        BodyRejection::Json {
+            rejection,
+            body: None,
+            path: None,
+        }
+    }
+
+    pub fn into_parts(self) -> (StatusCode, Json<T::Error>) {
+        match self {
+            BodyRejection::Json {
+                rejection,
+                path: Some(path),
+                ..
+            } => (
+                T::config().json_status,
+                Json(T::json_path_error(path, rejection)),
+            ),
+            BodyRejection::Json {
+                rejection,
+                body: Some(body),
+                ..
+            } => (
+                T::config().json_status,
+                Json(T::json_error_with_body(rejection, body)),
+            ),
+            BodyRejection::Json { rejection, .. } => (
+                json_rejection_status::<T>(&rejection),
+                Json(T::json_error(rejection)),
+            ),
+            BodyRejection::Validation(err) => validation_rejection::<T>(err),
+            BodyRejection::Error(status, error) => (status, Json(error)),
+        }
+    }
+}
+
+impl<T: BodyError> From<(StatusCode, Json<T::Error>)> for BodyRejection<T> {
+    fn from((status, Json(error)): (StatusCode, Json<T::Error>)) -> Self {
+        BodyRejection::Error(status, error)
+    }
+}
+
+impl<T: BodyError> From<BodyRejection<T>> for (StatusCode, Json<T::Error>) {
+    fn from(rejection: BodyRejection<T>) -> Self {
+        rejection.into_parts()
+    }
+}
+
+/// A rejection that splits into a status and a serializable error, lets wrappers like
+/// `Negotiate` take both the `(StatusCode, Json<R>)` tuple and `BodyRejection<T>`.
+pub trait RejectionParts {
+    type Error: Serialize;
+
+    fn rejection_parts(self) -> (StatusCode, Json<Self::Error>);
+}
+
+impl<R: Serialize> RejectionParts for (StatusCode, Json<R>) {
+    type Error = R;
+
+    fn rejection_parts(self) -> (StatusCode, Json<Self::Error>) {
+        self
+    }
+}
+
+impl<T: BodyError> RejectionParts for BodyRejection<T> {
+    type Error = T::Error;
+
+    fn rejection_parts(self) -> (StatusCode, Json<Self::Error>) {
+        self.into_parts()
+    }
+}
+
+impl<T: BodyError> IntoResponse for BodyRejection<T> {
+    fn into_response(self) -> Response {
+        let fields = match &self {
+            BodyRejection::Validation(err) if T::config().validation_errors_header => {
+                validation_errors_header(err, T::config().schema_key)
+            }
+            _ => None,
+        };
+
+        let (status, Json(error)) = self.into_parts();
+        let mut res = T::into_response(status, error);
+
+        if let Some(fields) = fields {
+            res.headers_mut().insert(X_VALIDATION_ERRORS, fields);
+        }
+
+        res
+    }
+}
+
+const X_VALIDATION_ERRORS: HeaderName = HeaderName::from_static("x-validation-errors");
+
+/// Longest `X-Validation-Errors` value, well below the usual 8 KiB header limits.
+const VALIDATION_ERRORS_HEADER_BYTES: usize = 1024;
+
+/// Comma separated failing fields, whole fields are dropped past the length limit.
+fn validation_errors_header(err: &ValidationErrors, schema_key: &str) -> Option<HeaderValue> {
+    let mut fields = String::new();
+    for field in flatten_errors_with(err, schema_key).keys() {
+        if fields.len() + field.len() + 1 > VALIDATION_ERRORS_HEADER_BYTES {
+            break;
+        }
+
+        if !fields.is_empty() {
+            fields.push(',');
+        }
+        fields.push_str(field);
+    }
+
+    HeaderValue::try_from(fields).ok()
+}
+
+impl<T: BodyError> std::fmt::Debug for BodyRejection<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BodyRejection::Json { rejection, .. } => {
+                f.debug_tuple("Json").field(rejection).finish()
+            }
+            BodyRejection::Validation(err) => f.debug_tuple("Validation").field(err).finish(),
+            BodyRejection::Error(status, _) => f.debug_tuple("Error").field(status).finish(),
+        }
+    }
+}
+
+/// Buffers and parses a JSON body as `T`, reporting failures through the hooks of `E`.
+async fn json_body<S, T, E>(req: Request, state: &S) -> Result<T, BodyRejection<E>>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+    E: BodyError,
+{
+    json_body_with::<S, E, _>(req, state, parse_json::<T, E>).await
+}
+
+/// Buffers a JSON body and hands it to `parse`, reporting failures before that through the
+/// hooks of `E`.
+async fn json_body_with<S, E, R>(
+    req: Request,
+    state: &S,
+    parse: impl FnOnce(&[u8]) -> Result<R, BodyRejection<E>>,
+) -> Result<R, BodyRejection<E>>
+where
+    S: Send + Sync,
+    E: BodyError,
+{
+    let req = limit_body::<E>(req)?;
+
+    // These rarely carry a body (or a `Content-Type`), an empty one goes to `empty_body`
+    let bodyless = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    let json = !E::config().require_content_type || json_content_type(req.headers());
+    let missing_content_type =
+        || BodyRejection::json(JsonRejection::from(MissingJsonContentType::default()));
+
+    if !json && !bodyless {
+        return Err(missing_content_type());
+    }
+
+    #[cfg(feature = "compression")]
+    let encoding = req
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_ascii_lowercase());
+
+    #[cfg(not(feature = "buffer-pool"))]
+    let bytes = Bytes::from_request(req, state)
+        .await
+        .map_err(bytes_rejection::<E>)?;
+
+    #[cfg(feature = "buffer-pool")]
+    let bytes = {
+        let _ = state;
+        pooled_body::<E>(req).await?
+    };
+
+    #[cfg(feature = "compression")]
+    let decoded = decompress::<E>(encoding.as_deref(), &bytes)?;
+
+    #[cfg(feature = "compression")]
+    let bytes = decoded.as_deref().unwrap_or(&bytes);
+
+    #[cfg(not(feature = "compression"))]
+    let bytes = &bytes[..];
+
+    // An empty body goes through the `empty_body` fallback of `parse`, `EmptyBody::Reject`
+    // included, which fails through `empty_body_error`
+    if !json && !(bodyless && bytes.trim_ascii().is_empty()) {
+        return Err(missing_content_type());
+    }
+
+    parse(bytes)
+}
+
+#[cfg(all(feature = "debug-echo", not(debug_assertions)))]
+compile_error!(
+    "the `debug-echo` feature echoes request bodies and is only allowed in debug builds"
+);
+
+/// Echoed bodies are cut to this many bytes.
+#[cfg(feature = "debug-echo")]
+const ECHO_BYTES: usize = 4096;
+
+/// The `debug-echo` feature also needs `AXUM_PLUS_DEBUG_ECHO=1` at runtime.
+#[cfg(feature = "debug-echo")]
+fn debug_echo_enabled() -> bool {
+    static ENABLED: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var("AXUM_PLUS_DEBUG_ECHO").is_ok_and(|value| value == "1"))
+}
+
+#[cfg(feature = "debug-echo")]
+fn echoed_body(bytes: &[u8]) -> String {
+    let mut echoed = String::from_utf8_lossy(&bytes[..bytes.len().min(ECHO_BYTES)]).into_owned();
+    if bytes.len() > ECHO_BYTES {
+        echoed.push_str("...");
+    }
+    echoed
+}
+
+/// Deserializes and validates a JSON payload like `Body<T>` without an axum request, for
+/// tower services outside the router.
+pub fn validate_json<T>(bytes: &[u8]) -> Result<T, (StatusCode, Json<T::Error>)>
+where
+    T: DeserializeOwned + Validate + BodyError,
+{
+    validated_json::<T>(bytes).map_err(BodyRejection::into_parts)
+}
+
+/// Core of `validate_json`, keeping why the payload was rejected for `Body<T>`.
+fn validated_json<T>(bytes: &[u8]) -> Result<T, BodyRejection<T>>
+where
+    T: DeserializeOwned + Validate + BodyError,
+{
+    let body = deserialize_json::<T>(bytes)?;
+
+    #[cfg(feature = "metrics")]
+    let start = std::time::Instant::now();
+
+    let validation = body.validate();
+
+    #[cfg(feature = "metrics")]
+    record_seconds::<T>("axum_plus_validate_seconds", start);
+
+    validated::<T>(body, validation)
+}
+
+/// Parses a JSON payload as `T`, recording the deserialize metric and tracing failures.
+fn deserialize_json<T>(bytes: &[u8]) -> Result<T, BodyRejection<T>>
+where
+    T: DeserializeOwned + BodyError,
+{
+    #[cfg(feature = "metrics")]
+    let start = std::time::Instant::now();
+
+    let body = parse_json::<T, T>(bytes);
+
+    #[cfg(feature = "metrics")]
+    record_seconds::<T>("axum_plus_deserialize_seconds", start);
+
+    #[cfg(feature = "trace")]
+    if body.is_err() {
+        trace_rejection::<T>("json", 0);
+    }
+
+    body
+}
+
+/// Turns the outcome of validating `body` into the rejection of `Body<T>`, tracing failures.
+fn validated<T: BodyError>(
+    body: T,
+    validation: Result<(), ValidationErrors>,
+) -> Result<T, BodyRejection<T>> {
+    if let Err(err) = validation {
+        #[cfg(feature = "trace")]
+        trace_rejection::<T>(
+            "validate",
+            flatten_errors(&err).values().map(Vec::len).sum(),
+        );
+
+        return Err(BodyRejection::Validation(err));
+    };
+
+    Ok(body)
+}
+
+/// Checks shared by every JSON body before deserialization (BOM, UTF-8 and duplicate keys),
+/// returns the bytes left to parse.
+fn check_json<E: BodyError>(bytes: &[u8]) -> Result<&[u8], BodyRejection<E>> {
+    // A UTF-8 BOM is tolerated and stripped, UTF-16/32 bodies fail the UTF-8 check below
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+
+    if let Err(err) = std::str::from_utf8(bytes) {
+        return Err(BodyRejection::Error(
+            E::config().json_status,
+            E::utf8_error(err),
+        ));
+    }
+
+    // An empty body is left to the `empty_body` fallback
+    if E::config().reject_duplicate_keys && !bytes.trim_ascii().is_empty() {
+        if let Err(rejection) = Json::<UniqueKeys>::from_bytes(bytes) {
+            return Err(BodyRejection::Json {
+                rejection,
+                body: Some(Bytes::copy_from_slice(bytes)),
+                path: None,
+            });
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Parses a buffered JSON body as `T`, reporting failures through the hooks of `E`.
+fn parse_json<T, E>(bytes: &[u8]) -> Result<T, BodyRejection<E>>
+where
+    T: DeserializeOwned,
+    E: BodyError,
+{
+    #[cfg(feature = "debug-echo")]
+    if debug_echo_enabled() {
+        return read_json::<T, E>(bytes).map_err(|rejection| {
+            let (status, Json(error)) = rejection.into_parts();
+            BodyRejection::Error(status, E::echo_body(error, &echoed_body(bytes)))
+        });
+    }
+
+    read_json::<T, E>(bytes)
+}
+
+/// `parse_json` without the `debug-echo` echo.
+fn read_json<T, E>(bytes: &[u8]) -> Result<T, BodyRejection<E>>
+where
+    T: DeserializeOwned,
+    E: BodyError,
+{
+    let bytes = check_json::<E>(bytes)?;
+    let config = E::config();
+
+    if bytes.trim_ascii().is_empty() {
+        let fallback = config.empty_body.json().map(Json::<T>::from_bytes);
+        if let Some(Ok(Json(body))) = fallback {
+            return Ok(body);
+        }
+
+        return Err(BodyRejection::Error(
+            config.json_status,
+            E::empty_body_error(),
+        ));
+    }
+
+    // Only scanned when a limit is set, serde_json enforces its own depth limit
+    let shape = match (config.max_depth, config.max_items) {
+        (None, None) => None,
+        (max_depth, max_items) => json_shape(bytes, max_depth, max_items),
+    };
+
+    match shape {
+        Some(JsonShape::TooDeep) => {
+            let error = E::depth_error(config.max_depth.unwrap_or_default());
+            return Err(BodyRejection::Error(config.json_status, error));
+        }
+        Some(JsonShape::TooManyItems) => {
+            let error = E::items_error(config.max_items.unwrap_or_default());
+            return Err(BodyRejection::Error(config.validate_status, error));
+        }
+        None => {}
+    }
+
+    // simd-json parses in place, so it needs a mutable copy of the body. Only the successful
+    // parse goes through it, failures are parsed again below for the usual rejections.
+    #[cfg(feature = "simd")]
+    if let Ok(body) = simd_json::serde::from_slice::<T>(&mut bytes.to_vec()) {
+        return Ok(body);
+    }
+
+    #[cfg(feature = "path-errors")]
+    {
+        let deserializer = &mut serde_json::Deserializer::from_slice(bytes);
+        let path = match serde_path_to_error::deserialize(&mut *deserializer) {
+            Ok(body) => match deserializer.end() {
+                Ok(()) => return Ok(body),
+                Err(_) => None,
+            },
+            Err(err) if err.path().iter().next().is_some() => Some(err.path().to_string()),
+            Err(_) => None,
+        };
+
+        // Only reached on failure, parse again to hand `BodyError` the usual rejection
+        if let (Some(path), Err(rejection)) = (path, Json::<T>::from_bytes(bytes)) {
+            return Err(BodyRejection::Json {
+                rejection,
+                body: Some(Bytes::copy_from_slice(bytes)),
+                path: Some(path),
+            });
+        }
+    }
+
+    let Json(body) = Json::<T>::from_bytes(bytes).map_err(|rejection| BodyRejection::Json {
+        rejection,
+        body: Some(Bytes::copy_from_slice(bytes)),
+        path: None,
+    })?;
+
+    Ok(body)
+}
+
+pub(crate) enum JsonShape {
+    TooDeep,
+    TooManyItems,
+}
+
+/// Scans the nesting of arrays and objects and the length of top-level arrays (the root array
+/// or an array field of the root object) without parsing, strings are skipped. Stops at the
+/// first top-level array over `max_items` so huge arrays aren't walked, nested arrays such as
+/// the `tags` of one item are left to `Validate`.
+pub(crate) fn json_shape(
+    bytes: &[u8],
+    max_depth: Option<usize>,
+    max_items: Option<usize>,
+) -> Option<JsonShape> {
+    // Items counted in every open top-level array, `None` for objects and nested arrays
+    let mut open = Vec::<Option<usize>>::new();
+    let (mut in_string, mut escaped) = (false, false);
+    // A counted array was just opened and its first item isn't seen yet
+    let mut first = false;
+
+    for byte in bytes {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        if first && !byte.is_ascii_whitespace() {
+            first = false;
+            if *byte != b']' && count_item(&mut open, max_items) {
+                return Some(JsonShape::TooManyItems);
+            }
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                if max_depth == Some(open.len()) {
+                    return Some(JsonShape::TooDeep);
+                }
+
+                // The root, or a field of the root object which is the only `None` at depth 1
+                let top_level = open.is_empty() || (open.len() == 1 && open[0].is_none());
+                let counted = *byte == b'[' && top_level && max_items.is_some();
+
+                open.push(counted.then_some(0));
+                first = counted;
+            }
+            b']' | b'}' => {
+                open.pop();
+            }
+            b',' => {
+                if count_item(&mut open, max_items) {
+                    return Some(JsonShape::TooManyItems);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Counts an item of the innermost open array if it's counted, `true` once over `max_items`.
+fn count_item(open: &mut [Option<usize>], max_items: Option<usize>) -> bool {
+    match (open.last_mut(), max_items) {
+        (Some(Some(items)), Some(max_items)) => {
+            *items += 1;
+            *items > max_items
+        }
+        _ => false,
+    }
+}
+
+/// Buffers kept by `Body<T>` with the `buffer-pool` feature, bodies are read into a leased
+/// buffer that goes back to the pool once parsed.
+#[cfg(feature = "buffer-pool")]
+static BUFFER_POOL: std::sync::Mutex<Vec<Vec<u8>>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(feature = "buffer-pool")]
+const POOLED_BUFFERS: usize = 256;
+
+/// Bigger buffers are dropped instead of pooled so one large body doesn't pin its memory.
+#[cfg(feature = "buffer-pool")]
+const MAX_POOLED_CAPACITY: usize = 1024 * 1024;
+
+#[cfg(feature = "buffer-pool")]
+struct PooledBuffer(Vec<u8>);
+
+#[cfg(feature = "buffer-pool")]
+impl PooledBuffer {
+    fn lease() -> Self {
+        let buffer = BUFFER_POOL
+            .lock()
+            .ok()
+            .and_then(|mut pool| pool.pop())
+            .unwrap_or_default();
+
+        PooledBuffer(buffer)
+    }
+}
+
+#[cfg(feature = "buffer-pool")]
+impl std::ops::Deref for PooledBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "buffer-pool")]
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        if self.0.capacity() > MAX_POOLED_CAPACITY {
+            return;
+        }
+
+        let mut buffer = std::mem::take(&mut self.0);
+        buffer.clear();
+
+        if let Ok(mut pool) = BUFFER_POOL.lock() {
+            if pool.len() < POOLED_BUFFERS {
+                pool.push(buffer);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "buffer-pool")]
+async fn pooled_body<E: BodyError>(
+    req: Request,
+) -> Result<PooledBuffer, (StatusCode, Json<E::Error>)> {
+    let mut buffer = PooledBuffer::lease();
+    // Keeps the `DefaultBodyLimit` of the request, 2 MiB unless configured
+    let mut stream = req.into_limited_body().into_data_stream();
+
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|err| {
+            let status = match (E::config().max_bytes, length_limit_exceeded(&err)) {
+                (Some(limit), true) => return (PAYLOAD_TOO_LARGE, Json(E::limit_error(limit))),
+                // Over a `DefaultBodyLimit`, which `BodyConfig::max_bytes` doesn't know about
+                (None, true) => PAYLOAD_TOO_LARGE,
+                (_, false) => BAD_REQUEST,
+            };
+
+            let err = parse_error("body", f!("Failed to buffer the request body: {}", err));
+            (status, Json(E::validate_error(err)))
+        })?;
+
+        buffer.0.extend_from_slice(&chunk);
+    }
+
+    Ok(buffer)
+}
+
+/// Whether reading the body failed on a `Limited` wrapper, possibly nested in `axum::Error`s.
+#[cfg(feature = "buffer-pool")]
+fn length_limit_exceeded(err: &axum::Error) -> bool {
+    let err = err as &(dyn std::error::Error + 'static);
+    std::iter::successors(Some(err), |err| err.source())
+        .any(|err| err.is::<http_body_util::LengthLimitError>())
+}
+
+#[cfg(feature = "utoipa")]
+impl<T: utoipa::PartialSchema> utoipa::PartialSchema for Body<T> {
+    fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+        T::schema()
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl<T: utoipa::ToSchema> utoipa::ToSchema for Body<T> {
+    fn name() -> Cow<'static, str> {
+        T::name()
+    }
+
+    fn schemas(
+        schemas: &mut Vec<(
+            String,
+            utoipa::openapi::RefOr<utoipa::openapi::schema::Schema>,
+        )>,
+    ) {
+        T::schemas(schemas)
+    }
+}
+
+/// Documents the `json_status` and `validate_status` rejections of a validated extractor
+/// with the `BodyError::Error` schema, e.g. `responses((status = 201), BodyRejections<CreateUser>)`.
+///
+/// `Body<T>` implements `IntoResponses` the same way, so `responses(Body<CreateUser>)` works
+/// too. When both statuses are equal they are documented by a single response.
+#[cfg(feature = "utoipa")]
+pub struct BodyRejections<T>(PhantomData<T>);
+
+#[cfg(feature = "utoipa")]
+impl<T> utoipa::IntoResponses for BodyRejections<T>
+where
+    T: BodyError,
+    T::Error: utoipa::ToSchema,
+{
+    fn responses() -> BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::response::Response>>
+    {
+        use utoipa::{
+            openapi::{ContentBuilder, Ref, ResponseBuilder},
+            ToSchema,
+        };
+
+        let content = ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name(<T::Error as ToSchema>::name())))
+            .build();
+
+        let response = |description: &str| {
+            ResponseBuilder::new()
+                .description(description)
+                .content("application/json", content.clone())
+                .build()
+                .into()
+        };
+
+        let config = T::config();
+        let (json, validate) = (config.json_status, config.validate_status);
+        let mut responses = BTreeMap::new();
+        // Sharing a status, a single entry has to document both rejections
+        if json == validate {
+            responses.insert(
+                json.as_u16().to_string(),
+                response("Malformed request payload or failed validation"),
+            );
+        } else {
+            responses.insert(
+                json.as_u16().to_string(),
+                response("Malformed request payload"),
+            );
+            responses.insert(
+                validate.as_u16().to_string(),
+                response("Request payload failed validation"),
+            );
+        }
+
+        responses
+    }
+}
+
+#[cfg(feature = "utoipa")]
+impl<T> utoipa::IntoResponses for Body<T>
+where
+    T: BodyError,
+    T::Error: utoipa::ToSchema,
+{
+    fn responses() -> BTreeMap<String, utoipa::openapi::RefOr<utoipa::openapi::response::Response>>
+    {
+        <BodyRejections<T> as utoipa::IntoResponses>::responses()
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for Body<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + 'static + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = BodyRejection<T>;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let names = T::config().captured_headers;
+        if names.is_empty() {
+            return validated_body::<S, T>(req, state).await.map(Body);
+        }
+
+        let captured = captured_headers(req.headers(), names);
+
+        validated_body::<S, T>(req, state)
+            .await
+            .map(Body)
+            .map_err(|rejection| {
+                let (status, Json(error)) = rejection.into_parts();
+                BodyRejection::Error(status, T::with_headers(error, &captured))
+            })
+    }
+}
+
+/// Deserializes the borrowing form of a `BodyRef` type and drops it, an owned stand-in so
+/// `Json::from_bytes` classifies a failed parse into the usual `JsonRejection`.
+#[cfg(feature = "borrow")]
+struct Borrowed<T>(PhantomData<T>);
+
+#[cfg(feature = "borrow")]
+impl<'de, T> Deserialize<'de> for Borrowed<T>
+where
+    T: yoke::Yokeable<'de>,
+    <T as yoke::Yokeable<'de>>::Output: Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <T as yoke::Yokeable<'de>>::Output::deserialize(deserializer)?;
+        Ok(Borrowed(PhantomData))
+    }
+}
+
+/// Cause of a `Body::try_extract` failure.
+#[derive(Debug)]
+pub enum BodyFailure {
+    Json(JsonRejection),
+    Validation(ValidationErrors),
+    /// Size limit, encoding or charset failures, already rendered through `BodyError`.
+    Other(Response),
+}
+
+impl<T> Body<T>
+where
+    T: Send + 'static + DeserializeOwned + Validate + BodyError,
+{
+    /// Extracts and validates `T` like the `FromRequest` impl but hands back the cause instead
+    /// of a response, for custom extractors and middleware.
+    pub async fn try_extract<S: Send + Sync>(req: Request, state: &S) -> Result<T, BodyFailure> {
+        validated_body::<S, T>(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                BodyRejection::Json { rejection, .. } => BodyFailure::Json(rejection),
+                BodyRejection::Validation(err) => BodyFailure::Validation(err),
+                BodyRejection::Error(status, error) => {
+                    BodyFailure::Other(T::into_response(status, error))
+                }
+            })
+    }
+}
+
+/// Copies the `names` headers, requests are consumed while reading the body.
+fn captured_headers(headers: &HeaderMap, names: &[&str]) -> HeaderMap {
+    let mut captured = HeaderMap::new();
+    for name in names {
+        for value in headers.get_all(*name) {
+            if let Ok(name) = HeaderName::try_from(*name) {
+                captured.append(name, value.clone());
+            }
+        }
+    }
+    captured
+}
+
+async fn validated_body<S, T>(req: Request, state: &S) -> Result<T, BodyRejection<T>>
+where
+    S: Send + Sync,
+    T: Send + 'static + DeserializeOwned + Validate + BodyError,
+{
+    let skip_validation = req.extensions().get::<SkipValidation>() == Some(&SkipValidation(true));
+
+    #[cfg(feature = "timeout")]
+    let timeout = match skip_validation {
+        true => None,
+        false => req
+            .extensions()
+            .get::<ValidationTimeout>()
+            .map(|ValidationTimeout(timeout)| *timeout)
+            .or(T::config().validate_timeout),
+    };
+
+    // Only parses when validation is skipped or moves to the blocking pool below
+    #[cfg(not(feature = "timeout"))]
+    let parse_only = skip_validation;
+
+    #[cfg(feature = "timeout")]
+    let parse_only = skip_validation || timeout.is_some();
+
+    // `Body<T>` only buffers in front of the `validate_json` core, which records the parse and
+    // validation metrics and traces
+    let body = json_body_with::<S, T, _>(req, state, |bytes| {
+        Ok(match parse_only {
+            true => deserialize_json::<T>(bytes),
+            false => validated_json::<T>(bytes),
+        })
+    })
+    .await;
+
+    #[cfg(feature = "trace")]
+    if body.is_err() {
+        trace_rejection::<T>("json", 0);
+    }
+
+    let body = body??;
+
+    #[cfg(feature = "timeout")]
+    if let Some(timeout) = timeout {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+
+        let (body, validation) = validate_with_timeout(body, timeout).await?;
+
+        #[cfg(feature = "metrics")]
+        record_seconds::<T>("axum_plus_validate_seconds", start);
+
+        return validated::<T>(body, validation);
+    }
+
+    Ok(body)
+}
+
+#[cfg(feature = "borrow")]
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for BodyRef<T>
+where
+    S: Send + Sync,
+    T: for<'a> yoke::Yokeable<'a> + BodyError + Send,
+    for<'a> <T as yoke::Yokeable<'a>>::Output: Deserialize<'a> + Validate,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let req = limit_body::<T>(req)?;
+
+        if T::config().require_content_type && !json_content_type(req.headers()) {
+            let rejection = JsonRejection::from(MissingJsonContentType::default());
+            return Err((
+                T::config().content_type_status,
+                Json(T::json_error(rejection)),
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(bytes_rejection::<T>)?;
+
+        // Same checks as `Body<T>`, which may strip a BOM off the front
+        let checked = check_json::<T>(&bytes).map_err(BodyRejection::into_parts)?;
+        let offset = bytes.len() - checked.len();
+
+        // An empty body is parsed as `BodyConfig::empty_body` says, like `Body<T>`
+        let empty = bytes[offset..].trim_ascii().is_empty();
+        let cart = match (empty, T::config().empty_body.json()) {
+            (true, Some(fallback)) => fallback.to_vec(),
+            (true, None) => return Err((T::config().json_status, Json(T::empty_body_error()))),
+            (false, _) => {
+                // `Bytes` isn't `StableDeref`, this copies unless the buffer is uniquely owned
+                let mut cart = Vec::from(bytes);
+                cart.drain(..offset);
+                cart
+            }
+        };
+
+        let body = yoke::Yoke::<T, Vec<u8>>::try_attach_to_cart(cart, |bytes| {
+            serde_json::from_slice(bytes).map_err(|err| {
+                // The `empty_body` fallback didn't fit `T`
+                if empty {
+                    return T::empty_body_error();
+                }
+
+                match Json::<Borrowed<T>>::from_bytes(bytes) {
+                    Err(rejection) => {
+                        T::json_error_with_body(rejection, Bytes::copy_from_slice(bytes))
+                    }
+                    // Not reached, `Borrowed<T>` fails on the same bytes
+                    Ok(_) => T::validate_error(parse_error("body", err.to_string())),
+                }
+            })
+        })
+        .map_err(|error| (T::config().json_status, Json(error)))?;
+
+        if let Err(err) = body.get().validate() {
+            return Err(validation_rejection::<T>(err));
+        }
+
+        Ok(BodyRef(body))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for BodyVec<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let items = json_body::<S, Vec<T>, T>(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let errors = validate_each(&items);
+        if !errors.is_empty() {
+            return Err((T::config().validate_status, Json(errors)).into_response());
+        }
+
+        Ok(BodyVec(items))
+    }
+}
+
+/// Validates every item, collecting the `BodyError::validate_error` of each invalid one by index.
+pub fn validate_each<T: Validate + BodyError>(items: &[T]) -> BTreeMap<usize, T::Error> {
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| {
+            let err = item.validate().err()?;
+            Some((index, T::validate_error(fail_fast_errors::<T>(err))))
+        })
+        .collect()
+}
+
+/// Validates on the blocking pool so a slow validator neither stalls the worker nor escapes
+/// the timeout.
+#[cfg(feature = "timeout")]
+async fn validate_with_timeout<T>(
+    body: T,
+    timeout: Duration,
+) -> Result<(T, Result<(), ValidationErrors>), BodyRejection<T>>
+where
+    T: Send + 'static + Validate + BodyError,
+{
+    let task = tokio::task::spawn_blocking(move || {
+        let validation = body.validate();
+        (body, validation)
+    });
+
+    match tokio::time::timeout(timeout, task).await {
+        Ok(Ok(validated)) => Ok(validated),
+        // Blocking tasks can't be cancelled, only a panicking validator ends up here
+        Ok(Err(err)) => std::panic::resume_unwind(err.into_panic()),
+        Err(_) => Err(BodyRejection::Error(
+            REQUEST_TIMEOUT,
+            T::timeout_error(timeout),
+        )),
+    }
+}
+
+/// Deserialization time includes buffering the body.
+#[cfg(feature = "metrics")]
+fn record_seconds<T>(name: &'static str, start: std::time::Instant) {
+    metrics::histogram!(name, "type" => type_name::<T>()).record(start.elapsed().as_secs_f64());
+}
+
+#[cfg(feature = "trace")]
+fn trace_rejection<T>(kind: &'static str, errors: usize) {
+    tracing::info!(
+        target: "axum_plus::rejection",
+        payload = type_name::<T>(),
+        kind,
+        errors,
+        "Rejected request payload"
+    );
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for LocalizedBody<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let resolver = req.extensions().get::<Resolver>().cloned();
+        let body = json_body::<S, T, T>(req, state)
+            .await
+            .map_err(BodyRejection::into_parts)?;
+
+        if let Err(mut err) = body.validate() {
+            if let Some(Resolver(resolver)) = resolver {
+                resolve_messages(&mut err, &*resolver);
+            }
+
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(LocalizedBody(body))
+    }
+}
+
+fn resolve_messages(err: &mut ValidationErrors, resolver: &dyn MessageResolver) {
+    use validator::ValidationErrorsKind::*;
+
+    for kind in err.0.values_mut() {
+        match kind {
+            Field(errors) => {
+                for error in errors {
+                    if let Some(message) = resolver.message(&error.code, &error.params) {
+                        error.message = Some(Cow::Owned(message));
+                    }
+                }
+            }
+            Struct(errors) => resolve_messages(errors, resolver),
+            List(errors) => {
+                for errors in errors.values_mut() {
+                    resolve_messages(errors, resolver);
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for BodyContext<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + BodyError + ValidateContext,
+    T: for<'a> ValidateArgs<'a, Args = &'a T::Context>,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(context) = req.extensions().get::<T::Context>().cloned() else {
+            tracing::error!(
+                "Failed to  extract {}, is it added to the request extensions",
+                type_name::<T::Context>()
+            );
+
+            let error = parse_error("context", string!("Unknown error occurred!"));
+            return Err((INTERNAL_SERVER_ERROR, Json(T::validate_error(error))));
+        };
+
+        let body = json_body::<S, T, T>(req, state)
+            .await
+            .map_err(BodyRejection::into_parts)?;
+
+        if let Err(err) = body.validate_with_args(&context) {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(BodyContext(body))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for StateValidate<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + BodyError,
+    T: for<'a> ValidateArgs<'a, Args = &'a S>,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let body = json_body::<S, T, T>(req, state)
+            .await
+            .map_err(BodyRejection::into_parts)?;
+
+        if let Err(err) = body.validate_with_args(state) {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(StateValidate(body))
+    }
+}
+
+#[cfg(feature = "jsonschema")]
+impl<T> JsonSchema<T> {
+    pub fn new(schema: &serde_json::Value) -> Result<Self, jsonschema::ValidationError<'static>> {
+        Ok(JsonSchema {
+            validator: jsonschema::validator_for(schema)?,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Messages of every violation by JSON pointer, empty when `instance` is valid.
+    pub fn violations(&self, instance: &serde_json::Value) -> BTreeMap<String, Vec<String>> {
+        let mut violations = BTreeMap::<String, Vec<String>>::new();
+        for error in self.validator.iter_errors(instance) {
+            violations
+                .entry(error.instance_path.to_string())
+                .or_default()
+                .push(error.to_string());
+        }
+        violations
+    }
+}
+
+#[cfg(feature = "jsonschema")]
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for SchemaBody<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + 'static + DeserializeOwned + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(Static(schema)) = req.extensions().get::<Static<JsonSchema<T>>>().copied() else {
+            tracing::error!(
+                "Failed to  extract {}, is it added via StaticLayer",
+                type_name::<JsonSchema<T>>()
+            );
+
+            let error = parse_error("schema", string!("Unknown error occurred!"));
+            return Err((INTERNAL_SERVER_ERROR, Json(T::validate_error(error))));
+        };
+
+        let value = json_body::<S, serde_json::Value, T>(req, state)
+            .await
+            .map_err(BodyRejection::into_parts)?;
+
+        let violations = schema.violations(&value);
+        if !violations.is_empty() {
+            return Err((
+                T::config().validate_status,
+                Json(T::schema_error(violations)),
+            ));
+        }
+
+        let body = serde_json::from_value::<T>(value).map_err(|err| {
+            let error = T::validate_error(parse_error("body", err.to_string()));
+            (T::config().json_status, Json(error))
+        })?;
+
+        Ok(SchemaBody(body))
+    }
+}
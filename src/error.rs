@@ -0,0 +1,653 @@
+//! `BodyError`, its `BodyConfig` and the default error payloads the extractors reject with.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
+use axum::{
+    body::Bytes,
+    extract::rejection::{BytesRejection, FormRejection, JsonRejection, PathRejection},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std_plus::{f, string};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::{reject, BAD_REQUEST, UNAUTHORIZED, UNPROCESSABLE_ENTITY, UNSUPPORTED_MEDIA_TYPE};
+
+/// Maps extraction failures of a validated extractor into a serializable error, settings such
+/// as statuses and limits are grouped in `BodyConfig`. The hooks are the same whatever
+/// features are enabled, those of an extractor behind a disabled feature are never called.
+pub trait BodyError {
+    type Error: Serialize;
+
+    fn json_error(rejection: JsonRejection) -> Self::Error;
+
+    fn validate_error(err: ValidationErrors) -> Self::Error;
+
+    /// Settings read by the extractors rejecting with this type, see `BodyConfig`.
+    fn config() -> BodyConfig {
+        BodyConfig::default()
+    }
+
+    /// Like `json_error` but also receives the buffered body that failed to parse, only
+    /// called for syntax and data errors since the body is never read otherwise.
+    fn json_error_with_body(rejection: JsonRejection, _body: Bytes) -> Self::Error {
+        Self::json_error(rejection)
+    }
+
+    /// Called instead of `json_error_with_body` with the path of the field that failed to
+    /// deserialize (e.g. `items[0].price`), only with the `path-errors` feature.
+    fn json_path_error(_path: String, rejection: JsonRejection) -> Self::Error {
+        Self::json_error(rejection)
+    }
+
+    /// Called on every `Body<T>` rejection with the `BodyConfig::captured_headers`, e.g. to
+    /// echo a correlation id in the error payload.
+    fn with_headers(error: Self::Error, _headers: &HeaderMap) -> Self::Error {
+        error
+    }
+
+    /// Called when a JSON body isn't valid UTF-8, before it's parsed. A leading UTF-8 BOM is
+    /// stripped, any other BOM ends up here.
+    fn utf8_error(err: std::str::Utf8Error) -> Self::Error {
+        let message = f!(
+            "Body is not valid UTF-8, invalid byte at offset {}",
+            err.valid_up_to()
+        );
+        Self::validate_error(parse_error("body", message))
+    }
+
+    /// Called when validation exceeds the `BodyConfig::validate_timeout`.
+    fn timeout_error(timeout: Duration) -> Self::Error {
+        Self::validate_error(parse_error(
+            "body",
+            f!("Validation took longer than {}ms", timeout.as_millis()),
+        ))
+    }
+
+    /// Attaches the received body (lossy UTF-8, cut to 4 KiB) to the error of a body that
+    /// failed to parse, e.g. as an extra field. Only called with the `debug-echo` feature, which
+    /// refuses to compile without `debug_assertions`, and with `AXUM_PLUS_DEBUG_ECHO=1` set.
+    /// Echoed bodies may carry credentials or personal data, never enable it outside local
+    /// development.
+    fn echo_body(error: Self::Error, _body: &str) -> Self::Error {
+        error
+    }
+
+    /// Renders a `Body<T>` (or `BodyVec<T>`) rejection, override it for plain text or a bare
+    /// status. Extractors rejecting with `(StatusCode, Json<Self::Error>)` always send JSON.
+    fn into_response(status: StatusCode, error: Self::Error) -> Response {
+        (status, Json(error)).into_response()
+    }
+
+    /// Called when the body exceeds `BodyConfig::max_bytes`.
+    fn limit_error(limit: usize) -> Self::Error {
+        Self::validate_error(parse_error(
+            "body",
+            f!("Body exceeds the limit of {} bytes", limit),
+        ))
+    }
+
+    /// Combines the rejections of `BodyQuery<Self, Q>` into one payload, by default an object
+    /// with the failing `body` and `query` errors.
+    fn merge<E: Serialize>(body: Option<Self::Error>, query: Option<E>) -> serde_json::Value {
+        let mut merged = serde_json::Map::new();
+
+        if let Some(body) = body {
+            let body = serde_json::to_value(body).unwrap_or_default();
+            merged.insert(string!("body"), body);
+        }
+
+        if let Some(query) = query {
+            let query = serde_json::to_value(query).unwrap_or_default();
+            merged.insert(string!("query"), query);
+        }
+
+        serde_json::Value::Object(merged)
+    }
+
+    /// Called when the body is nested deeper than `BodyConfig::max_depth`.
+    fn depth_error(max_depth: usize) -> Self::Error {
+        Self::validate_error(parse_error(
+            "body",
+            f!("JSON is nested deeper than {} levels", max_depth),
+        ))
+    }
+
+    /// Called when a top-level array holds more than `BodyConfig::max_items`.
+    fn items_error(max_items: usize) -> Self::Error {
+        Self::validate_error(parse_error(
+            "body",
+            f!("Arrays may hold at most {} items", max_items),
+        ))
+    }
+
+    /// Called when an empty body (e.g. a `POST` with `Content-Length: 0`) isn't accepted as
+    /// the `BodyConfig::empty_body` fallback, instead of a serde EOF error.
+    fn empty_body_error() -> Self::Error {
+        Self::validate_error(parse_error(
+            "body",
+            string!("Request body is empty, expected a JSON payload"),
+        ))
+    }
+
+    /// Response with the same shape as the extractor rejections, for errors raised in handlers.
+    fn reject(status: StatusCode, err: Self::Error) -> (StatusCode, Json<Self::Error>) {
+        reject(status, err)
+    }
+
+    /// Rejects like a failed validation of the extractor, for business rules checked in
+    /// handlers.
+    fn reject_validation(err: ValidationErrors) -> (StatusCode, Json<Self::Error>) {
+        validation_rejection::<Self>(err)
+    }
+
+    /// Called by `Query<T>` when the query string can't be deserialized.
+    /// Defaults to reporting the message under the `query` key via `validate_error`.
+    fn query_error(err: serde_urlencoded::de::Error) -> Self::Error {
+        Self::validate_error(parse_error("query", err.to_string()))
+    }
+
+    /// Called by `QsQuery<T>` when the query string can't be deserialized.
+    fn qs_error(message: String) -> Self::Error {
+        Self::validate_error(parse_error("query", message))
+    }
+
+    /// Called by `Form<T>` when the body can't be read or deserialized.
+    fn form_error(rejection: FormRejection) -> Self::Error {
+        Self::validate_error(parse_error("form", rejection.body_text()))
+    }
+
+    /// Called by `Path<T>` when the matched path params are missing or can't be deserialized.
+    fn path_error(rejection: PathRejection) -> Self::Error {
+        Self::validate_error(parse_error("path", rejection.body_text()))
+    }
+
+    /// Called by `Header<T>` when required headers are missing or can't be deserialized.
+    fn header_error(err: serde_urlencoded::de::Error) -> Self::Error {
+        Self::validate_error(parse_error("headers", err.to_string()))
+    }
+
+    /// Called when the request body can't be buffered, the rejection keeps the status of
+    /// the `BytesRejection` (e.g. `PAYLOAD_TOO_LARGE`).
+    fn bytes_error(rejection: BytesRejection) -> Self::Error {
+        Self::validate_error(parse_error("body", rejection.body_text()))
+    }
+
+    /// Called by `Cbor<T>` when the body isn't valid CBOR for `Self`.
+    fn cbor_error(message: String) -> Self::Error {
+        Self::validate_error(parse_error("body", message))
+    }
+
+    /// Called by `MsgPack<T>` when the body isn't valid MessagePack for `Self`.
+    fn msgpack_error(message: String) -> Self::Error {
+        Self::validate_error(parse_error("body", message))
+    }
+
+    /// Called by `MultipartForm<T>` when the body isn't valid multipart or the text parts can't
+    /// be deserialized.
+    fn multipart_error(message: String) -> Self::Error {
+        Self::validate_error(parse_error("multipart", message))
+    }
+
+    /// Called by `SchemaBody<T>` with the messages of every schema violation by the JSON
+    /// pointer of the offending value (`""` for the document itself). By default each one is
+    /// reported under `body` with a `pointer` param.
+    fn schema_error(violations: BTreeMap<String, Vec<String>>) -> Self::Error {
+        let mut errors = ValidationErrors::new();
+        for (pointer, messages) in violations {
+            for message in messages {
+                let message = f!("{}: {}", pointer, message);
+                let mut error = ValidationError::new("schema").with_message(Cow::Owned(message));
+                error.add_param(Cow::Borrowed("pointer"), &pointer);
+                errors.add("body", error);
+            }
+        }
+        Self::validate_error(errors)
+    }
+
+    /// Called by `Bearer<T>` when the token is missing, malformed or fails verification.
+    fn token_error(message: String) -> Self::Error {
+        Self::validate_error(parse_error("authorization", message))
+    }
+
+    /// Called when the request `Content-Type` doesn't match what the extractor expects,
+    /// the rejection is sent with `UNSUPPORTED_MEDIA_TYPE`.
+    fn content_type_error(expected: &'static str) -> Self::Error {
+        Self::validate_error(parse_error(
+            "content-type",
+            f!("Expected request with `Content-Type: {}`", expected),
+        ))
+    }
+}
+
+/// Settings of a `BodyError` type, returned by `BodyError::config` (or `DefaultError::config`).
+/// Every field exists whatever features are enabled, a setting of a disabled feature is
+/// ignored. Override only what differs from the defaults:
+///
+/// ```
+/// use axum_plus::{BodyConfig, DefaultError, BAD_REQUEST};
+///
+/// struct Upload;
+///
+/// impl DefaultError for Upload {
+///     fn config() -> BodyConfig {
+///         BodyConfig {
+///             json_status: BAD_REQUEST,
+///             max_bytes: Some(64 * 1024),
+///             ..BodyConfig::default()
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BodyConfig {
+    /// Status sent when the payload can't be parsed, `BAD_REQUEST` by default.
+    pub json_status: StatusCode,
+
+    /// Status sent when the payload fails validation, `UNPROCESSABLE_ENTITY` by default.
+    pub validate_status: StatusCode,
+
+    /// Status sent when the `Content-Type` is missing or isn't JSON, `UNSUPPORTED_MEDIA_TYPE`
+    /// by default.
+    pub content_type_status: StatusCode,
+
+    /// Status `Bearer<T>` rejects with, for the token and for its claims, `UNAUTHORIZED` by
+    /// default.
+    pub token_status: StatusCode,
+
+    /// Whether `Body<T>` rejects requests without a JSON `Content-Type`, relax it for clients
+    /// that send `text/json` or no header at all, the body is then parsed regardless.
+    pub require_content_type: bool,
+
+    /// Rejects JSON objects repeating a key through `json_error`, at the cost of a second pass
+    /// over the body.
+    pub reject_duplicate_keys: bool,
+
+    /// Report only the first failing field instead of every validation error. `ValidationErrors`
+    /// is a `HashMap` without the declaration order, so the field kept is the alphabetically
+    /// first failing one (and its first error), e.g. `age` over `name`.
+    pub fail_fast: bool,
+
+    /// Headers copied before `Body<T>` reads the body and handed to `with_headers`, e.g.
+    /// `&["x-request-id"]`.
+    pub captured_headers: &'static [&'static str],
+
+    /// Adds an `X-Validation-Errors` header listing the failing fields (e.g. `name,items.0.sku`,
+    /// cut to 1 KiB) to `Body<T>` validation rejections, for clients that only read headers.
+    /// Not sent along `captured_headers`.
+    pub validation_errors_header: bool,
+
+    /// Maximum body size accepted by `Body<T>`, bigger bodies are rejected with
+    /// `PAYLOAD_TOO_LARGE` before deserialization. With `None` the `DefaultBodyLimit` of the
+    /// router still applies and its rejection goes through `bytes_error`.
+    pub max_bytes: Option<usize>,
+
+    /// Deepest nesting of arrays and objects `Body<T>` parses, deeper documents are rejected
+    /// through `depth_error` before deserialization. Off by default: serde_json already stops
+    /// at 128 levels with a `json_error`, so the extra scan of the body only pays off for a
+    /// lower limit or a dedicated error. Limits above 128 still fail in serde_json.
+    pub max_depth: Option<usize>,
+
+    /// Most items the root array or an array field of the root object may hold, e.g. the
+    /// `length(max = ..)` of a bulk `items` field. Checked while scanning the raw body so an
+    /// oversized array is rejected through `items_error` before a single item is deserialized,
+    /// arrays nested deeper are left to `Validate`.
+    pub max_items: Option<usize>,
+
+    /// What an empty or whitespace-only JSON body is parsed as before falling back to
+    /// `empty_body_error`, by default `{}` so types with all-optional fields accept it. Also
+    /// used for `GET`, `HEAD` and `OPTIONS` requests without a body, see `Body`.
+    pub empty_body: EmptyBody,
+
+    /// Key struct level `#[validate(schema(..))]` errors are reported under by the default
+    /// error types, `__all__` by default.
+    pub schema_key: &'static str,
+
+    /// Aborts `Body<T>` validation taking longer than this with `REQUEST_TIMEOUT` (with the
+    /// `timeout` feature). Validation then runs on tokio's blocking pool, a validator that
+    /// timed out keeps running there until it returns.
+    pub validate_timeout: Option<Duration>,
+}
+
+impl Default for BodyConfig {
+    fn default() -> Self {
+        BodyConfig {
+            json_status: BAD_REQUEST,
+            validate_status: UNPROCESSABLE_ENTITY,
+            content_type_status: UNSUPPORTED_MEDIA_TYPE,
+            token_status: UNAUTHORIZED,
+            require_content_type: true,
+            reject_duplicate_keys: false,
+            fail_fast: false,
+            captured_headers: &[],
+            validation_errors_header: false,
+            max_bytes: None,
+            max_depth: None,
+            max_items: None,
+            empty_body: EmptyBody::Object,
+            schema_key: SCHEMA_KEY,
+            validate_timeout: None,
+        }
+    }
+}
+
+/// Reason of a `JsonRejection`, stable across axum versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum JsonErrorKind {
+    /// No `application/json` `Content-Type`.
+    MissingContentType,
+    /// The body isn't valid JSON.
+    Syntax,
+    /// Valid JSON that doesn't match the target type.
+    Data,
+    /// A key rejected by `#[serde(deny_unknown_fields)]`, see `unknown_field`.
+    UnknownField,
+    /// The body couldn't be buffered.
+    Body,
+    Other,
+}
+
+pub fn classify(rejection: &JsonRejection) -> JsonErrorKind {
+    match rejection {
+        JsonRejection::MissingJsonContentType(_) => JsonErrorKind::MissingContentType,
+        JsonRejection::JsonSyntaxError(_) => JsonErrorKind::Syntax,
+        JsonRejection::JsonDataError(_) if unknown_field(rejection).is_some() => {
+            JsonErrorKind::UnknownField
+        }
+        JsonRejection::JsonDataError(_) => JsonErrorKind::Data,
+        JsonRejection::BytesRejection(_) => JsonErrorKind::Body,
+        _ => JsonErrorKind::Other,
+    }
+}
+
+/// `{ "field": ["Unknown field"] }` for a rejected unknown key, empty otherwise.
+pub(crate) fn unknown_field_messages(rejection: &JsonRejection) -> HashMap<String, Vec<String>> {
+    unknown_field(rejection)
+        .map(|field| HashMap::from([(field, vec![string!("Unknown field")])]))
+        .unwrap_or_default()
+}
+
+pub(crate) fn json_rejection_status<T: BodyError + ?Sized>(
+    rejection: &JsonRejection,
+) -> StatusCode {
+    match classify(rejection) {
+        JsonErrorKind::MissingContentType => T::config().content_type_status,
+        _ => T::config().json_status,
+    }
+}
+
+/// Name of the key a `#[serde(deny_unknown_fields)]` type rejected, from serde's
+/// "unknown field `name`" message.
+pub fn unknown_field(rejection: &JsonRejection) -> Option<String> {
+    let JsonRejection::JsonDataError(_) = rejection else {
+        return None;
+    };
+
+    let text = rejection.body_text();
+    let (_, rest) = text.split_once("unknown field `")?;
+    let (field, _) = rest.split_once('`')?;
+    Some(field.to_owned())
+}
+
+/// How `BodyConfig::empty_body` parses an empty or whitespace-only JSON body, including the
+/// missing body of a `GET`, `HEAD` or `OPTIONS` request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyBody {
+    /// Rejects it through `BodyError::empty_body_error`.
+    Reject,
+    /// Parses it as `null`.
+    Null,
+    /// Parses it as `{}`.
+    Object,
+}
+
+impl EmptyBody {
+    pub(crate) fn json(self) -> Option<&'static [u8]> {
+        match self {
+            EmptyBody::Reject => None,
+            EmptyBody::Null => Some(b"null"),
+            EmptyBody::Object => Some(b"{}"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DefaultBodyError {
+    pub message: String,
+
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub fields: HashMap<String, Vec<String>>,
+}
+
+/// Errors of a `validate()` run in a handler, sent as `UNPROCESSABLE_ENTITY` with the same
+/// `DefaultBodyError` body as the extractors. Struct level errors are reported under `__all__`,
+/// `for_body::<T>()` answers like a `Body<T>` rejection instead, honouring its `schema_key`.
+///
+/// ```
+/// use axum::response::{IntoResponse, Response};
+/// use axum_plus::ValidationResponse;
+/// use validator::Validate;
+///
+/// #[derive(Validate)]
+/// struct Rename {
+///     #[validate(length(min = 1, message = "name is required"))]
+///     name: String,
+/// }
+///
+/// async fn rename() -> Response {
+///     let rename = Rename { name: String::new() };
+///
+///     if let Err(err) = rename.validate() {
+///         return ValidationResponse(err).into_response();
+///     }
+///
+///     "renamed".into_response()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ValidationResponse(pub ValidationErrors);
+
+impl ValidationResponse {
+    pub fn with_status(self, status: StatusCode) -> Response {
+        (status, Json(self.error())).into_response()
+    }
+
+    /// Sends the errors through `T::validate_error` with the `validate_status` of `T::config()`,
+    /// exactly as `Body<T>` rejects a payload failing validation.
+    pub fn for_body<T: BodyError>(self) -> Response {
+        T::into_response(T::config().validate_status, T::validate_error(self.0))
+    }
+
+    fn error(self) -> DefaultBodyError {
+        DefaultBodyError {
+            message: string!("Invalid payload data!"),
+            fields: flatten_errors(&self.0).into_iter().collect(),
+        }
+    }
+}
+
+impl IntoResponse for ValidationResponse {
+    fn into_response(self) -> Response {
+        self.with_status(UNPROCESSABLE_ENTITY)
+    }
+}
+
+/// Opt-in marker, `impl DefaultError for T {}` gives `T` a `BodyError` impl that
+/// rejects with `DefaultBodyError`.
+///
+/// `config` and `with_headers` are forwarded by the blanket impl, so a type keeps the default
+/// payload while overriding its settings.
+pub trait DefaultError {
+    /// Forwarded to `BodyError::config`.
+    fn config() -> BodyConfig {
+        BodyConfig::default()
+    }
+
+    /// Forwarded to `BodyError::with_headers`.
+    fn with_headers(error: DefaultBodyError, _headers: &HeaderMap) -> DefaultBodyError {
+        error
+    }
+}
+
+impl<T: DefaultError> BodyError for T {
+    type Error = DefaultBodyError;
+
+    fn json_error(rejection: JsonRejection) -> Self::Error {
+        DefaultBodyError {
+            message: rejection.body_text(),
+            fields: unknown_field_messages(&rejection),
+        }
+    }
+
+    fn json_path_error(path: String, rejection: JsonRejection) -> Self::Error {
+        let message = rejection.body_text();
+
+        DefaultBodyError {
+            fields: HashMap::from([(path, vec![message.clone()])]),
+            message,
+        }
+    }
+
+    fn validate_error(err: ValidationErrors) -> Self::Error {
+        DefaultBodyError {
+            message: string!("Invalid payload data!"),
+            fields: flatten_errors_with(&err, <T as DefaultError>::config().schema_key)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn config() -> BodyConfig {
+        <T as DefaultError>::config()
+    }
+
+    fn with_headers(error: DefaultBodyError, headers: &HeaderMap) -> DefaultBodyError {
+        <T as DefaultError>::with_headers(error, headers)
+    }
+}
+
+pub(crate) fn validation_rejection<T: BodyError + ?Sized>(
+    err: ValidationErrors,
+) -> (StatusCode, Json<T::Error>) {
+    let error = T::validate_error(fail_fast_errors::<T>(err));
+    (T::config().validate_status, Json(error))
+}
+
+pub(crate) fn fail_fast_errors<T: BodyError + ?Sized>(err: ValidationErrors) -> ValidationErrors {
+    match T::config().fail_fast {
+        true => first_error(err),
+        false => err,
+    }
+}
+
+/// Keeps the first error of the alphabetically first field, the map has no declaration order.
+fn first_error(mut err: ValidationErrors) -> ValidationErrors {
+    use validator::ValidationErrorsKind::*;
+
+    let Some(field) = err.0.keys().min().cloned() else {
+        return err;
+    };
+
+    err.0.retain(|key, _| *key == field);
+    match err.0.get_mut(&field) {
+        Some(Field(errors)) => errors.truncate(1),
+        Some(Struct(errors)) => {
+            **errors = first_error(std::mem::replace(&mut **errors, ValidationErrors::new()));
+        }
+        Some(List(errors)) => {
+            let first = errors.keys().next().copied();
+            errors.retain(|index, _| Some(*index) == first);
+
+            for errors in errors.values_mut() {
+                **errors = first_error(std::mem::replace(&mut **errors, ValidationErrors::new()));
+            }
+        }
+        None => {}
+    }
+
+    err
+}
+
+pub(crate) fn parse_error(key: &'static str, message: String) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        key,
+        ValidationError::new("parse").with_message(Cow::Owned(message)),
+    );
+    errors
+}
+
+/// Flattens nested `ValidationErrors` into `{ "path": ["message"] }` with dotted paths such
+/// as `address.zip` or `items.0.sku`, errors without a message report their code. Struct level
+/// `#[validate(schema(..))]` errors are kept under `__all__` (`address.__all__` when nested).
+pub fn flatten_errors(err: &ValidationErrors) -> BTreeMap<String, Vec<String>> {
+    flatten_errors_with(err, SCHEMA_KEY)
+}
+
+/// Key validator reports `#[validate(schema(..))]` errors under.
+const SCHEMA_KEY: &str = "__all__";
+
+/// `flatten_errors` with struct level errors under `schema_key`, e.g. `"_schema"`.
+pub fn flatten_errors_with(
+    err: &ValidationErrors,
+    schema_key: &str,
+) -> BTreeMap<String, Vec<String>> {
+    let mut out = BTreeMap::<String, Vec<String>>::new();
+    walk_errors(None, err, &mut |path, error| {
+        let path = match schema_key == SCHEMA_KEY {
+            true => path.to_string(),
+            false => path
+                .split('.')
+                .map(|segment| match segment {
+                    SCHEMA_KEY => schema_key,
+                    segment => segment,
+                })
+                .collect::<Vec<_>>()
+                .join("."),
+        };
+
+        out.entry(path).or_default().push(error_message(error));
+    });
+    out
+}
+
+/// Visits every field error with its dotted path, e.g. `address.zip` or `items.0.sku`.
+pub(crate) fn walk_errors(
+    prefix: Option<&str>,
+    err: &ValidationErrors,
+    visit: &mut dyn FnMut(&str, &ValidationError),
+) {
+    use validator::ValidationErrorsKind::*;
+
+    for (field, kind) in err.0.iter() {
+        let path = match prefix {
+            Some(prefix) => f!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+
+        match kind {
+            Field(errors) => {
+                for error in errors {
+                    visit(&path, error);
+                }
+            }
+            Struct(errors) => walk_errors(Some(&path), errors, visit),
+            List(errors) => {
+                for (index, errors) in errors {
+                    walk_errors(Some(&f!("{}.{}", path, index)), errors, visit);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn error_message(error: &ValidationError) -> String {
+    error.message.as_ref().unwrap_or(&error.code).to_string()
+}
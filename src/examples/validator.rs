@@ -1,7 +1,8 @@
-use super::make_error;
-use std::{borrow::Cow, collections::HashMap};
+use axum::extract::rejection::JsonRejection;
+use axum_plus::BodyError;
+use serde::Serialize;
 use std_plus::{new, string};
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
 #[derive(Debug, Validate, new)]
 struct User {
@@ -21,15 +22,37 @@ struct OldUser {
     age: i32,
 }
 
-fn main() {
-    let user = User::new(string!("West"), OldUser::new(string!("East"), 2));
+#[derive(Debug, Serialize)]
+struct UserError {
+    reason: &'static str,
 
-    type Store = HashMap<Cow<'static, str>, Vec<(Cow<'static, str>, Cow<'static, str>)>>;
-    let mut store: Store = HashMap::new();
+    #[serde(skip_serializing_if = "Option::is_none")]
+    messages: Option<ValidationErrors>,
+}
 
-    let _ = user
-        .validate()
-        .map_err(|err| make_error(None, &err, &mut store));
+impl BodyError for User {
+    type Error = UserError;
+
+    fn json_error(_: JsonRejection) -> Self::Error {
+        UserError {
+            reason: "Failed to parse the body into valid json!",
+            messages: None,
+        }
+    }
+
+    fn validate_error(err: ValidationErrors) -> Self::Error {
+        UserError {
+            reason: "Invalid payload data!",
+            messages: Some(err),
+        }
+    }
+}
+
+fn main() {
+    let user = User::new(string!("West"), OldUser::new(string!("East"), 2));
 
-    println!("{:?}", store);
+    if let Err(err) = user.validate() {
+        let error = User::validate_error(err);
+        println!("{}", serde_json::to_string_pretty(&error).unwrap());
+    }
 }
@@ -0,0 +1,523 @@
+//! Multipart, NDJSON, CBOR and MessagePack extractors.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{BodyDataStream, Bytes},
+    extract::{FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json, RequestExt,
+};
+use futures_util::{stream::BoxStream, Stream, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use validator::Validate;
+
+use crate::{fail_fast_errors, has_content_type, parse_error, BodyError, UNSUPPORTED_MEDIA_TYPE};
+
+#[cfg(feature = "multipart")]
+use std_plus::f;
+
+#[cfg(any(feature = "cbor", feature = "msgpack", feature = "multipart"))]
+use crate::validation_rejection;
+#[cfg(feature = "multipart")]
+use crate::{limit_body, PAYLOAD_TOO_LARGE};
+
+/// Validated `multipart/form-data`, text parts fill the fields of `T` like `Form<T>` and file
+/// parts are handed to `MultipartFiles::insert_file` before validation.
+#[cfg(feature = "multipart")]
+pub struct MultipartForm<T>(pub T);
+
+#[cfg(feature = "cbor")]
+pub struct Cbor<T>(pub T);
+
+#[cfg(feature = "msgpack")]
+pub struct MsgPack<T>(pub T);
+
+/// File part of a `MultipartForm<T>`.
+#[cfg(feature = "multipart")]
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub bytes: Bytes,
+}
+
+/// Receives the file parts of a `MultipartForm<T>`, usually into `#[serde(skip)]` fields so
+/// `Validate` can check them alongside the text fields.
+#[cfg(feature = "multipart")]
+pub trait MultipartFiles {
+    /// Stores the file part named `field`, returning it back rejects the request.
+    fn insert_file(&mut self, field: &str, file: UploadedFile) -> Result<(), UploadedFile>;
+
+    /// Maximum size of a single part, the whole body is bounded by `BodyConfig::max_bytes`.
+    fn max_field_bytes() -> Option<usize> {
+        None
+    }
+
+    /// Constraints of the file part named `field`, checked while it streams in: a wrong type
+    /// is rejected with `UNSUPPORTED_MEDIA_TYPE` before reading it and an oversized file with
+    /// `PAYLOAD_TOO_LARGE` as soon as it crosses the limit.
+    fn file_rule(_field: &str) -> Option<FileRule> {
+        None
+    }
+}
+
+/// Per field constraints of a `MultipartForm<T>` file, see `MultipartFiles::file_rule`.
+#[cfg(feature = "multipart")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileRule {
+    /// Accepted `Content-Type`s, e.g. `image/png` or `image/*`, any when empty.
+    pub content_types: &'static [&'static str],
+    pub max_bytes: Option<usize>,
+}
+
+#[cfg(feature = "multipart")]
+impl FileRule {
+    fn accepts(&self, content_type: Option<&str>) -> bool {
+        if self.content_types.is_empty() {
+            return true;
+        }
+
+        let Some(content_type) = content_type.and_then(|value| value.split(';').next()) else {
+            return false;
+        };
+
+        let content_type = content_type.trim().to_ascii_lowercase();
+        self.content_types
+            .iter()
+            .any(|accepted| match accepted.strip_suffix("/*") {
+                Some(kind) => content_type
+                    .split_once('/')
+                    .is_some_and(|(given, _)| given.eq_ignore_ascii_case(kind)),
+                None => content_type.eq_ignore_ascii_case(accepted),
+            })
+    }
+}
+
+#[cfg(feature = "multipart")]
+const MULTIPART_CONTENT_TYPE: &str = "multipart/form-data";
+
+#[cfg(feature = "multipart")]
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for MultipartForm<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError + MultipartFiles,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_content_type(req.headers(), MULTIPART_CONTENT_TYPE) {
+            let error = T::content_type_error(MULTIPART_CONTENT_TYPE);
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)));
+        }
+
+        let req = limit_body::<T>(req)?;
+        let mut multipart = axum::extract::Multipart::from_request(req, state)
+            .await
+            .map_err(|rejection| {
+                (
+                    T::config().json_status,
+                    Json(T::multipart_error(rejection.body_text())),
+                )
+            })?;
+
+        let multipart_error =
+            |err: axum::extract::multipart::MultipartError| match T::config().max_bytes {
+                Some(limit) if err.status() == PAYLOAD_TOO_LARGE => {
+                    (PAYLOAD_TOO_LARGE, Json(T::limit_error(limit)))
+                }
+                _ => (
+                    T::config().json_status,
+                    Json(T::multipart_error(err.body_text())),
+                ),
+            };
+
+        let mut pairs = Vec::new();
+        let mut files = Vec::new();
+
+        while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+            let Some(name) = field.name().map(str::to_owned) else {
+                continue;
+            };
+
+            let file_name = field.file_name().map(str::to_owned);
+            let content_type = field.content_type().map(str::to_owned);
+            let rule = T::file_rule(&name);
+
+            if let Some(rule) = rule {
+                if file_name.is_none() {
+                    let message = f!("Field `{}` must be a file", name);
+                    return Err((T::config().json_status, Json(T::multipart_error(message))));
+                }
+
+                if !rule.accepts(content_type.as_deref()) {
+                    let message = f!(
+                        "File `{}` must be one of {}",
+                        name,
+                        rule.content_types.join(", ")
+                    );
+                    return Err((UNSUPPORTED_MEDIA_TYPE, Json(T::multipart_error(message))));
+                }
+            }
+
+            let field_limit = match rule.and_then(|rule| rule.max_bytes) {
+                Some(limit) => Some(limit),
+                None => T::max_field_bytes(),
+            };
+
+            let mut bytes = Vec::new();
+            while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                bytes.extend_from_slice(&chunk);
+
+                if let Some(limit) = field_limit.filter(|limit| bytes.len() > *limit) {
+                    return Err((PAYLOAD_TOO_LARGE, Json(T::limit_error(limit))));
+                }
+            }
+
+            if file_name.is_none() {
+                let value = String::from_utf8(bytes).map_err(|_| {
+                    let message = f!("Field `{}` is not valid UTF-8", name);
+                    (T::config().json_status, Json(T::multipart_error(message)))
+                })?;
+                pairs.push((name, value));
+                continue;
+            }
+
+            let file = UploadedFile {
+                file_name,
+                content_type,
+                bytes: Bytes::from(bytes),
+            };
+            files.push((name, file));
+        }
+
+        // Same string to scalar coercion as `Form<T>`
+        let encoded = serde_urlencoded::to_string(&pairs).unwrap_or_default();
+        let mut form = serde_urlencoded::from_str::<T>(&encoded).map_err(|err| {
+            (
+                T::config().json_status,
+                Json(T::multipart_error(err.to_string())),
+            )
+        })?;
+
+        for (name, file) in files {
+            if form.insert_file(&name, file).is_err() {
+                let message = f!("Unexpected file field `{}`", name);
+                return Err((T::config().json_status, Json(T::multipart_error(message))));
+            }
+        }
+
+        if let Err(err) = form.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(MultipartForm(form))
+    }
+}
+
+const NDJSON_CONTENT_TYPES: [&str; 3] = [
+    "application/x-ndjson",
+    "application/ndjson",
+    "application/jsonl",
+];
+
+/// Failure of a single line of a newline-delimited JSON body, lines start at 1.
+#[derive(Debug, Serialize)]
+pub struct LineError<E> {
+    pub line: usize,
+    pub error: E,
+
+    #[serde(skip)]
+    parse: bool,
+}
+
+/// Newline-delimited JSON body where every line is parsed and validated, rejects with the
+/// errors of every failing line. Buffers the whole body within the route's `DefaultBodyLimit`,
+/// use `NdJsonStream` for large uploads.
+pub struct NdJson<T>(pub Vec<T>);
+
+/// Streaming `NdJson`, lines are parsed and validated as they arrive so only the current
+/// line is buffered. `BodyConfig::max_bytes` bounds the length of a single line, the route's
+/// `DefaultBodyLimit` the whole body.
+pub struct NdJsonStream<T: BodyError>(pub BoxStream<'static, Result<T, LineError<T::Error>>>);
+
+impl<T: BodyError> Stream for NdJsonStream<T> {
+    type Item = Result<T, LineError<T::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+fn parse_line<T>(line: usize, bytes: &[u8]) -> Result<T, LineError<T::Error>>
+where
+    T: DeserializeOwned + Validate + BodyError,
+{
+    if let Some(limit) = T::config().max_bytes.filter(|limit| bytes.len() > *limit) {
+        return Err(LineError {
+            line,
+            error: T::limit_error(limit),
+            parse: true,
+        });
+    }
+
+    let Json(value) = Json::<T>::from_bytes(bytes).map_err(|rejection| LineError {
+        line,
+        error: T::json_error(rejection),
+        parse: true,
+    })?;
+
+    if let Err(err) = value.validate() {
+        return Err(LineError {
+            line,
+            error: T::validate_error(fail_fast_errors::<T>(err)),
+            parse: false,
+        });
+    }
+
+    Ok(value)
+}
+
+struct Lines {
+    stream: BodyDataStream,
+    buffer: Vec<u8>,
+    // Bytes of `buffer` already searched for a newline
+    scanned: usize,
+    line: usize,
+    done: bool,
+}
+
+fn ndjson_lines<T>(body: axum::body::Body) -> BoxStream<'static, Result<T, LineError<T::Error>>>
+where
+    T: DeserializeOwned + Validate + BodyError + Send + 'static,
+    T::Error: Send + 'static,
+{
+    let lines = Lines {
+        stream: body.into_data_stream(),
+        buffer: Vec::new(),
+        scanned: 0,
+        line: 0,
+        done: false,
+    };
+
+    futures_util::stream::unfold(lines, |mut lines| async move {
+        loop {
+            let unscanned = &lines.buffer[lines.scanned..];
+            if let Some(index) = unscanned.iter().position(|byte| *byte == b'\n') {
+                let rest = lines.buffer.split_off(lines.scanned + index + 1);
+                let line = std::mem::replace(&mut lines.buffer, rest);
+                lines.scanned = 0;
+                lines.line += 1;
+
+                // Blank lines carry no record
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+
+                return Some((parse_line::<T>(lines.line, line.trim_ascii()), lines));
+            }
+
+            if lines.done {
+                if lines.buffer.trim_ascii().is_empty() {
+                    return None;
+                }
+
+                let line = std::mem::take(&mut lines.buffer);
+                lines.line += 1;
+                return Some((parse_line::<T>(lines.line, line.trim_ascii()), lines));
+            }
+
+            // Only the next chunk is searched once it arrives
+            lines.scanned = lines.buffer.len();
+
+            // Bounds the buffer while no newline has arrived yet, complete lines are checked
+            // by `parse_line`
+            if let Some(limit) = T::config()
+                .max_bytes
+                .filter(|limit| lines.buffer.len() > *limit)
+            {
+                lines.done = true;
+                lines.buffer.clear();
+
+                let error = LineError {
+                    line: lines.line + 1,
+                    error: T::limit_error(limit),
+                    parse: true,
+                };
+                return Some((Err(error), lines));
+            }
+
+            match lines.stream.next().await {
+                Some(Ok(chunk)) => lines.buffer.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    lines.done = true;
+                    lines.buffer.clear();
+
+                    let error = LineError {
+                        line: lines.line + 1,
+                        error: T::validate_error(parse_error("body", err.to_string())),
+                        parse: true,
+                    };
+                    return Some((Err(error), lines));
+                }
+                None => lines.done = true,
+            }
+        }
+    })
+    .boxed()
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for NdJsonStream<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError + 'static,
+    T::Error: Send + 'static,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, _: &S) -> Result<Self, Self::Rejection> {
+        let headers = req.headers();
+        if !NDJSON_CONTENT_TYPES
+            .iter()
+            .any(|expected| has_content_type(headers, expected))
+        {
+            let error = T::content_type_error(NDJSON_CONTENT_TYPES[0]);
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)));
+        }
+
+        Ok(NdJsonStream(ndjson_lines(req.into_limited_body())))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for NdJson<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError + 'static,
+    T::Error: Send + 'static,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let headers = req.headers();
+        if !NDJSON_CONTENT_TYPES
+            .iter()
+            .any(|expected| has_content_type(headers, expected))
+        {
+            let error = T::content_type_error(NDJSON_CONTENT_TYPES[0]);
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)).into_response());
+        }
+
+        // Buffered within the `DefaultBodyLimit` of the route before any line is parsed
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|rejection| (rejection.status(), Json(T::bytes_error(rejection))))
+            .map_err(IntoResponse::into_response)?;
+        let mut lines = ndjson_lines::<T>(axum::body::Body::from(bytes));
+
+        let mut values = Vec::new();
+        let mut errors = Vec::new();
+
+        while let Some(line) = lines.next().await {
+            match line {
+                Ok(value) => values.push(value),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(NdJson(values));
+        }
+
+        let status = match errors.iter().any(|error| error.parse) {
+            true => T::config().json_status,
+            false => T::config().validate_status,
+        };
+
+        Err((status, Json(errors)).into_response())
+    }
+}
+
+#[cfg(feature = "cbor")]
+const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+#[cfg(feature = "cbor")]
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for Cbor<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_content_type(req.headers(), CBOR_CONTENT_TYPE) {
+            let error = T::content_type_error(CBOR_CONTENT_TYPE);
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)));
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|rejection| (rejection.status(), Json(T::bytes_error(rejection))))?;
+
+        let body = ciborium::from_reader::<T, _>(&bytes[..]).map_err(|err| {
+            (
+                T::config().json_status,
+                Json(T::cbor_error(err.to_string())),
+            )
+        })?;
+
+        if let Err(err) = body.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(Cbor(body))
+    }
+}
+
+#[cfg(feature = "msgpack")]
+const MSGPACK_CONTENT_TYPES: [&str; 2] = ["application/msgpack", "application/x-msgpack"];
+
+#[cfg(feature = "msgpack")]
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for MsgPack<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let headers = req.headers();
+        if !MSGPACK_CONTENT_TYPES
+            .iter()
+            .any(|expected| has_content_type(headers, expected))
+        {
+            let error = T::content_type_error(MSGPACK_CONTENT_TYPES[0]);
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)));
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|rejection| (rejection.status(), Json(T::bytes_error(rejection))))?;
+
+        let body = rmp_serde::from_slice::<T>(&bytes).map_err(|err| {
+            (
+                T::config().json_status,
+                Json(T::msgpack_error(err.to_string())),
+            )
+        })?;
+
+        if let Err(err) = body.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(MsgPack(body))
+    }
+}
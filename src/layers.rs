@@ -0,0 +1,320 @@
+//! Tower layers limiting bodies, validating responses and logging rejections.
+
+use std::{
+    any::type_name,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::Request,
+    response::{IntoResponse, Response},
+};
+use http_body_util::{BodyExt, Limited};
+use serde::de::DeserializeOwned;
+use std_plus::{f, new};
+use tower_layer::Layer;
+use tower_service::Service;
+use validator::Validate;
+
+use crate::{
+    content_length, json_content_type, BAD_REQUEST, INTERNAL_SERVER_ERROR, PAYLOAD_TOO_LARGE,
+};
+
+/// Rejects requests with a body over `limit` bytes with `PAYLOAD_TOO_LARGE` before the inner
+/// service runs. The `Content-Length` is checked first, then the body is buffered up to the
+/// limit so chunked requests without a length are caught too.
+#[derive(new, Clone)]
+pub struct BodySizeLimitLayer {
+    limit: usize,
+}
+
+impl<S> Layer<S> for BodySizeLimitLayer {
+    type Service = BodySizeLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        BodySizeLimit::new(inner, self.limit)
+    }
+}
+
+#[derive(new, Clone)]
+pub struct BodySizeLimit<S> {
+    inner: S,
+    limit: usize,
+}
+
+impl<S> Service<Request> for BodySizeLimit<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        // The clone isn't ready yet, keep the one `poll_ready` was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limit = self.limit;
+
+        Box::pin(async move {
+            let too_large = || {
+                let message = f!("Body exceeds the limit of {} bytes", limit);
+                (PAYLOAD_TOO_LARGE, message).into_response()
+            };
+
+            if content_length(req.headers()).is_some_and(|length| length > limit) {
+                return Ok(too_large());
+            }
+
+            let (parts, body) = req.into_parts();
+            let bytes = match Limited::new(body, limit).collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(err) if err.is::<http_body_util::LengthLimitError>() => return Ok(too_large()),
+                Err(err) => {
+                    let message = f!("Failed to buffer the request body: {}", err);
+                    return Ok((BAD_REQUEST, message).into_response());
+                }
+            };
+
+            let req = Request::from_parts(parts, axum::body::Body::from(bytes));
+            inner.call(req).await
+        })
+    }
+}
+
+/// Re-validates successful JSON responses as `T`, logging failures or replacing the response
+/// with `INTERNAL_SERVER_ERROR` in strict mode. Meant as a development/staging guardrail.
+pub struct ValidateResponseLayer<T> {
+    strict: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ValidateResponseLayer<T> {
+    pub fn new() -> Self {
+        ValidateResponseLayer {
+            strict: false,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+impl<T> Default for ValidateResponseLayer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ValidateResponseLayer<T> {
+    fn clone(&self) -> Self {
+        Self::new().strict(self.strict)
+    }
+}
+
+impl<S, T> Layer<S> for ValidateResponseLayer<T> {
+    type Service = ValidateResponse<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ValidateResponse {
+            inner,
+            strict: self.strict,
+            _marker: PhantomData,
+        }
+    }
+}
+
+pub struct ValidateResponse<S, T> {
+    inner: S,
+    strict: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<S: Clone, T> Clone for ValidateResponse<S, T> {
+    fn clone(&self) -> Self {
+        ValidateResponse {
+            inner: self.inner.clone(),
+            strict: self.strict,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<ReqBody, S, T> Service<Request<ReqBody>> for ValidateResponse<S, T>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    S::Future: Send + 'static,
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let future = self.inner.call(req);
+        let strict = self.strict;
+
+        Box::pin(async move {
+            let res = future.await?;
+            Ok(validate_response::<T>(res, strict).await)
+        })
+    }
+}
+
+async fn validate_response<T>(res: Response, strict: bool) -> Response
+where
+    T: DeserializeOwned + Validate,
+{
+    if !res.status().is_success() || !json_content_type(res.headers()) {
+        return res;
+    }
+
+    let (parts, body) = res.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            tracing::error!(
+                "Failed to buffer response for {}: {}",
+                type_name::<T>(),
+                err
+            );
+            return INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let result = serde_json::from_slice::<T>(&bytes)
+        .map_err(|err| err.to_string())
+        .and_then(|value| value.validate().map_err(|err| err.to_string()));
+
+    if let Err(err) = result {
+        tracing::error!(
+            "Response failed validation as {}: {}",
+            type_name::<T>(),
+            err
+        );
+
+        if strict {
+            return INTERNAL_SERVER_ERROR.into_response();
+        }
+    }
+
+    Response::from_parts(parts, axum::body::Body::from(bytes))
+}
+
+/// Response extension marking rejections generated by this crate, holding the type name of
+/// the extractor. `RejectionLogLayer` only logs responses carrying it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RejectedBy(pub &'static str);
+
+/// Emits a `tracing` error for every server error rejection generated by this crate (e.g. a
+/// missing `StaticLayer`), other 5xx responses are left alone. `sample(n)` logs one of every
+/// `n` such rejections.
+#[derive(Clone)]
+pub struct RejectionLogLayer {
+    sample: usize,
+    seen: Arc<AtomicUsize>,
+}
+
+impl RejectionLogLayer {
+    pub fn new() -> Self {
+        RejectionLogLayer {
+            sample: 1,
+            seen: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn sample(mut self, every: usize) -> Self {
+        self.sample = every.max(1);
+        self
+    }
+}
+
+impl Default for RejectionLogLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for RejectionLogLayer {
+    type Service = RejectionLog<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RejectionLog {
+            inner,
+            sample: self.sample,
+            seen: self.seen.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RejectionLog<S> {
+    inner: S,
+    sample: usize,
+    seen: Arc<AtomicUsize>,
+}
+
+impl<ReqBody, S> Service<Request<ReqBody>> for RejectionLog<S>
+where
+    S: Service<Request<ReqBody>, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let method = req.method().clone();
+        let uri = req.uri().clone();
+        let future = self.inner.call(req);
+        let sample = self.sample;
+        let seen = self.seen.clone();
+
+        Box::pin(async move {
+            let res = future.await?;
+
+            let rejected = res.extensions().get::<RejectedBy>();
+            if let Some(RejectedBy(extractor)) = rejected.filter(|_| res.status().is_server_error())
+            {
+                if seen.fetch_add(1, Ordering::Relaxed) % sample == 0 {
+                    tracing::error!(
+                        target: "axum_plus::rejection",
+                        extractor,
+                        status = res.status().as_u16(),
+                        %method,
+                        %uri,
+                        "Extractor rejected the request with a server error"
+                    );
+                }
+            }
+
+            Ok(res)
+        })
+    }
+}
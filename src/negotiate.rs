@@ -0,0 +1,433 @@
+//! Alternative error shapes: react-hook-form errors, RFC 9457 problems and `Negotiate<E>`.
+
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Request},
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        request::Parts,
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std_plus::{f, string};
+use validator::{Validate, ValidationErrors};
+
+use crate::{
+    error_message, validation_rejection, walk_errors, BodyError, RejectionParts, BAD_REQUEST,
+    INTERNAL_SERVER_ERROR, UNPROCESSABLE_ENTITY,
+};
+
+#[cfg(feature = "xml")]
+use std::any::type_name;
+
+/// `BodyError::Error` shaped like react-hook-form errors, `{ "field": { "type", "message" } }`
+/// with the first error of each field under its dotted path. JSON failures are reported
+/// under `root`.
+///
+/// ```
+/// use axum_plus::{BodyError, RhfError};
+/// use axum::extract::rejection::JsonRejection;
+/// use validator::ValidationErrors;
+///
+/// struct SignUp;
+///
+/// impl BodyError for SignUp {
+///     type Error = RhfError;
+///
+///     fn json_error(rejection: JsonRejection) -> Self::Error {
+///         RhfError::json(rejection)
+///     }
+///
+///     fn validate_error(err: ValidationErrors) -> Self::Error {
+///         RhfError::from(err)
+///     }
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+#[serde(transparent)]
+pub struct RhfError(pub BTreeMap<String, RhfField>);
+
+#[derive(Debug, Serialize)]
+pub struct RhfField {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+}
+
+impl RhfError {
+    pub fn json(rejection: JsonRejection) -> Self {
+        let field = RhfField {
+            kind: string!("json"),
+            message: rejection.body_text(),
+        };
+        RhfError(BTreeMap::from([(string!("root"), field)]))
+    }
+}
+
+impl From<ValidationErrors> for RhfError {
+    fn from(err: ValidationErrors) -> Self {
+        let mut fields = BTreeMap::new();
+        walk_errors(None, &err, &mut |path, error| {
+            fields.entry(path.to_string()).or_insert_with(|| RhfField {
+                kind: error.code.to_string(),
+                message: error_message(error),
+            });
+        });
+        RhfError(fields)
+    }
+}
+
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// RFC 7807 problem document, responds with its own `status` and `application/problem+json`.
+#[derive(Debug, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// Validation failures keyed by JSON pointer, e.g. `/address/zip`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub errors: BTreeMap<String, Vec<String>>,
+}
+
+impl Problem {
+    pub fn new(status: StatusCode) -> Self {
+        Problem {
+            kind: string!("about:blank"),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: None,
+            errors: BTreeMap::new(),
+        }
+    }
+
+    /// Malformed JSON, defaults to `BAD_REQUEST`. `ProblemJson` replaces the status with the
+    /// one of the rejection.
+    pub fn json(rejection: JsonRejection) -> Self {
+        Problem::new(BAD_REQUEST).with_detail(rejection.body_text())
+    }
+
+    /// Failed validation, defaults to `UNPROCESSABLE_ENTITY`. `ProblemJson` replaces the
+    /// status with the one of the rejection.
+    pub fn validate(err: ValidationErrors) -> Self {
+        let mut problem = Problem::new(UNPROCESSABLE_ENTITY).with_detail("Invalid payload data!");
+        walk_errors(None, &err, &mut |path, error| {
+            problem
+                .errors
+                .entry(f!("/{}", path.replace('.', "/")))
+                .or_default()
+                .push(error_message(error));
+        });
+        problem
+    }
+
+    pub fn with_status(mut self, status: StatusCode) -> Self {
+        self.title = status.canonical_reason().unwrap_or_default().to_string();
+        self.status = status.as_u16();
+        self
+    }
+
+    pub fn with_type(mut self, kind: impl Into<String>) -> Self {
+        self.kind = kind.into();
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(INTERNAL_SERVER_ERROR);
+        let mut res = (status, Json(self)).into_response();
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+        res
+    }
+}
+
+/// Wraps a validated extractor whose `BodyError::Error` is `Problem` so the rejection is
+/// sent as `application/problem+json`. The problem takes the status of the extractor's
+/// rejection, so the document and the response always agree.
+pub struct ProblemJson<E>(pub E);
+
+#[async_trait::async_trait]
+impl<S, E> FromRequest<S> for ProblemJson<E>
+where
+    S: Send + Sync,
+    E: FromRequest<S>,
+    E::Rejection: Into<(StatusCode, Json<Problem>)>,
+{
+    type Rejection = Problem;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        E::from_request(req, state)
+            .await
+            .map(ProblemJson)
+            .map_err(|rejection| {
+                let (status, Json(problem)) = rejection.into();
+                problem.with_status(status)
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, E> FromRequestParts<S> for ProblemJson<E>
+where
+    S: Send + Sync,
+    E: FromRequestParts<S>,
+    E::Rejection: Into<(StatusCode, Json<Problem>)>,
+{
+    type Rejection = Problem;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        E::from_request_parts(parts, state)
+            .await
+            .map(ProblemJson)
+            .map_err(|rejection| {
+                let (status, Json(problem)) = rejection.into();
+                problem.with_status(status)
+            })
+    }
+}
+
+/// Validates the value of any axum extractor implementing `HasValidate`, e.g.
+/// `Valid<axum::Json<T>>` or `Valid<axum::extract::Query<T>>`. Rejections of the inner
+/// extractor are sent unchanged, validation failures go through `BodyError`.
+pub struct Valid<E>(pub E);
+
+/// Exposes the value validated by `Valid<E>`.
+pub trait HasValidate {
+    type Inner;
+
+    fn inner(&self) -> &Self::Inner;
+}
+
+macro_rules! impl_has_validate {
+    ($($ty:path),*) => {
+        $(
+            impl<T> HasValidate for $ty {
+                type Inner = T;
+
+                fn inner(&self) -> &Self::Inner {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+impl_has_validate!(
+    axum::Json<T>,
+    axum::Form<T>,
+    axum::Extension<T>,
+    axum::extract::Query<T>,
+    axum::extract::Path<T>
+);
+
+#[async_trait::async_trait]
+impl<S, E> FromRequest<S> for Valid<E>
+where
+    S: Send + Sync,
+    E: FromRequest<S> + HasValidate,
+    E::Inner: Validate + BodyError,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let value = E::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if let Err(err) = value.inner().validate() {
+            return Err(validation_rejection::<E::Inner>(err).into_response());
+        }
+
+        Ok(Valid(value))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, E> FromRequestParts<S> for Valid<E>
+where
+    S: Send + Sync,
+    E: FromRequestParts<S> + HasValidate,
+    E::Inner: Validate + BodyError,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let value = E::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        if let Err(err) = value.inner().validate() {
+            return Err(validation_rejection::<E::Inner>(err).into_response());
+        }
+
+        Ok(Valid(value))
+    }
+}
+
+/// Wraps a validated extractor and sends its rejection in the format asked for by the
+/// `Accept` header: JSON by default, `text/plain` lists the messages one per line and
+/// `application/xml` is available with the `xml` feature. The supported media type with the
+/// highest quality value wins, the first one on ties, and `q=0` rules a type out.
+pub struct Negotiate<E>(pub E);
+
+enum ErrorFormat {
+    Json,
+    Text,
+    #[cfg(feature = "xml")]
+    Xml,
+}
+
+impl ErrorFormat {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let accept = headers
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        let mut best: Option<(f32, ErrorFormat)> = None;
+        for media in accept.split(',') {
+            let mut params = media.split(';');
+            let mime = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let quality = params
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .map_or(1.0, |(_, value)| value.trim().parse().unwrap_or(0.0));
+
+            // `q=0` marks the media type as not acceptable
+            if quality <= 0.0 {
+                continue;
+            }
+
+            let format = match mime.as_str() {
+                "application/json" | "application/*" | "*/*" => ErrorFormat::Json,
+                "text/plain" | "text/*" => ErrorFormat::Text,
+                #[cfg(feature = "xml")]
+                "application/xml" | "text/xml" => ErrorFormat::Xml,
+                _ => continue,
+            };
+
+            if best.as_ref().is_some_and(|(best, _)| *best >= quality) {
+                continue;
+            }
+
+            best = Some((quality, format));
+        }
+
+        best.map_or(ErrorFormat::Json, |(_, format)| format)
+    }
+
+    fn respond<E: Serialize>(self, status: StatusCode, error: E) -> Response {
+        match self {
+            ErrorFormat::Json => (status, Json(error)).into_response(),
+            ErrorFormat::Text => {
+                let mut lines = Vec::new();
+                let value = serde_json::to_value(&error).unwrap_or_default();
+                text_lines(None, &value, &mut lines);
+                (status, lines.join("\n")).into_response()
+            }
+            #[cfg(feature = "xml")]
+            ErrorFormat::Xml => match quick_xml::se::to_string_with_root("error", &error) {
+                Ok(xml) => (status, [(CONTENT_TYPE, "application/xml")], xml).into_response(),
+                Err(err) => {
+                    tracing::error!("Failed to serialize {} as xml: {}", type_name::<E>(), err);
+                    (status, Json(error)).into_response()
+                }
+            },
+        }
+    }
+}
+
+fn text_lines(path: Option<&str>, value: &serde_json::Value, lines: &mut Vec<String>) {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => {}
+        Value::Array(values) => {
+            for value in values {
+                text_lines(path, value, lines);
+            }
+        }
+        Value::Object(values) => {
+            for (key, value) in values {
+                let path = match path {
+                    Some(path) => f!("{}.{}", path, key),
+                    None => key.clone(),
+                };
+                text_lines(Some(&path), value, lines);
+            }
+        }
+        value => {
+            let message = match value {
+                Value::String(message) => message.clone(),
+                value => value.to_string(),
+            };
+
+            lines.push(match path {
+                Some(path) => f!("{}: {}", path, message),
+                None => message,
+            });
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, E> FromRequest<S> for Negotiate<E>
+where
+    S: Send + Sync,
+    E: FromRequest<S>,
+    E::Rejection: RejectionParts,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let format = ErrorFormat::from_headers(req.headers());
+        E::from_request(req, state)
+            .await
+            .map(Negotiate)
+            .map_err(|rejection| {
+                let (status, Json(error)) = rejection.rejection_parts();
+                format.respond(status, error)
+            })
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, E> FromRequestParts<S> for Negotiate<E>
+where
+    S: Send + Sync,
+    E: FromRequestParts<S>,
+    E::Rejection: RejectionParts,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let format = ErrorFormat::from_headers(&parts.headers);
+        E::from_request_parts(parts, state)
+            .await
+            .map(Negotiate)
+            .map_err(|rejection| {
+                let (status, Json(error)) = rejection.rejection_parts();
+                format.respond(status, error)
+            })
+    }
+}
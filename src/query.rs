@@ -0,0 +1,529 @@
+//! Query string, form, path and header extractors, and `All<T>` combining them.
+
+use std::{borrow::Cow, marker::PhantomData, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std_plus::{new, string};
+use validator::Validate;
+
+use crate::{
+    has_content_type, validation_rejection, Body, BodyError, BodyRejection, DefaultBodyError,
+    DefaultError, BAD_REQUEST, OK, UNSUPPORTED_MEDIA_TYPE,
+};
+
+pub struct Query<T>(pub T);
+
+/// `Query<T>` where params the client omitted are filled from the `QueryDefaults<T>` found in
+/// the request extensions, without it this behaves like `Query<T>`.
+pub struct DefaultedQuery<T>(pub T);
+
+/// `Query<T>` parsed with `serde_qs`, supporting nested maps (`filter[status]=a`) and arrays
+/// (`ids[]=1&ids[]=2` or `ids[0]=1&ids[1]=2`), brackets may be percent-encoded.
+#[cfg(feature = "qs")]
+pub struct QsQuery<T>(pub T);
+
+pub struct Form<T>(pub T);
+
+/// Validated JSON body and query string, failures of both are reported together.
+pub struct BodyQuery<B, Q>(pub B, pub Q);
+
+/// Runs every extractor of a tuple, e.g. `All<(Body<A>, Query<B>, Path<C>)>`, against its own
+/// copy of the buffered request and rejects with all of their errors at once, merged by `M`.
+pub struct All<T, M = KeyedRejections>(pub T, pub PhantomData<M>);
+
+pub struct Path<T>(pub T);
+
+/// Validated headers, each header maps to the field with the same name lowercased and
+/// with `-` replaced by `_`, e.g. `X-Api-Version` fills `x_api_version`.
+///
+/// A header sent several times is joined with `, ` into one value, like a single comma
+/// separated header. Values that aren't visible ASCII are skipped.
+pub struct Header<T>(pub T);
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Query<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        // A missing query string deserializes like an empty one
+        let query = parts.uri.query().unwrap_or_default();
+        let params = serde_urlencoded::from_str::<T>(query)
+            .map_err(|err| (T::config().json_status, Json(T::query_error(err))))?;
+
+        if let Err(err) = params.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(Query(params))
+    }
+}
+
+/// Server side defaults for `DefaultedQuery<T>`, added to the request extensions by a layer,
+/// e.g. `axum::Extension(QueryDefaults::new(&ListParams { per_page: 20, .. }))`.
+pub struct QueryDefaults<T> {
+    pairs: Arc<Vec<(String, String)>>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize> QueryDefaults<T> {
+    pub fn new(defaults: &T) -> Self {
+        let pairs = serde_urlencoded::to_string(defaults)
+            .and_then(|encoded| {
+                serde_urlencoded::from_str(&encoded).map_err(|err| {
+                    serde_urlencoded::ser::Error::Custom(Cow::Owned(err.to_string()))
+                })
+            })
+            .inspect_err(|err| tracing::error!("Failed to encode query defaults: {}", err))
+            .unwrap_or_default();
+
+        QueryDefaults {
+            pairs: Arc::new(pairs),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for QueryDefaults<T> {
+    fn clone(&self) -> Self {
+        QueryDefaults {
+            pairs: self.pairs.clone(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for DefaultedQuery<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError + 'static,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let mut pairs = serde_urlencoded::from_str::<Vec<(String, String)>>(query)
+            .map_err(|err| (T::config().json_status, Json(T::query_error(err))))?;
+
+        // Only the params the client omitted are filled
+        if let Some(defaults) = parts.extensions.get::<QueryDefaults<T>>() {
+            let omitted = defaults
+                .pairs
+                .iter()
+                .filter(|(key, _)| !pairs.iter().any(|(given, _)| given == key))
+                .cloned()
+                .collect::<Vec<_>>();
+            pairs.extend(omitted);
+        }
+
+        let encoded = serde_urlencoded::to_string(&pairs).unwrap_or_default();
+        let params = serde_urlencoded::from_str::<T>(&encoded)
+            .map_err(|err| (T::config().json_status, Json(T::query_error(err))))?;
+
+        if let Err(err) = params.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(DefaultedQuery(params))
+    }
+}
+
+/// `page` and `per_page` query params, extracted directly as `Pagination`. Omitted params
+/// come from the `PaginationDefaults` in the request extensions (page 1, 20 per page and at
+/// most 100 without it), a bigger `per_page` is clamped to the maximum and a zero one is
+/// rejected like any invalid `Query<T>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Validate)]
+pub struct Pagination {
+    #[validate(range(min = 1))]
+    pub page: u32,
+
+    #[validate(range(min = 1))]
+    pub per_page: u32,
+}
+
+impl Pagination {
+    /// Items to skip before this page.
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page.saturating_sub(1)) * u64::from(self.per_page)
+    }
+}
+
+impl DefaultError for Pagination {}
+
+/// Server side defaults for `Pagination`, added by a layer, e.g.
+/// `axum::Extension(PaginationDefaults::new(1, 50, 200))`.
+#[derive(new, Debug, Clone, Copy)]
+pub struct PaginationDefaults {
+    pub page: u32,
+    pub per_page: u32,
+    pub max_per_page: u32,
+}
+
+impl Default for PaginationDefaults {
+    fn default() -> Self {
+        PaginationDefaults::new(1, 20, 100)
+    }
+}
+
+#[derive(Deserialize)]
+struct PaginationParams {
+    page: Option<u32>,
+    per_page: Option<u32>,
+}
+
+#[async_trait::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Pagination {
+    type Rejection = (StatusCode, Json<DefaultBodyError>);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let defaults = parts
+            .extensions
+            .get::<PaginationDefaults>()
+            .copied()
+            .unwrap_or_default();
+
+        let query = parts.uri.query().unwrap_or_default();
+        let params = serde_urlencoded::from_str::<PaginationParams>(query)
+            .map_err(|err| (Self::config().json_status, Json(Self::query_error(err))))?;
+
+        let pagination = Pagination {
+            page: params.page.unwrap_or(defaults.page),
+            per_page: params
+                .per_page
+                .unwrap_or(defaults.per_page)
+                .min(defaults.max_per_page),
+        };
+
+        if let Err(err) = pagination.validate() {
+            return Err(validation_rejection::<Self>(err));
+        };
+
+        Ok(pagination)
+    }
+}
+
+/// List envelope sent as `{ "data": [..], "page": 1, "per_page": 20, "total": 42 }`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Paginated<T> {
+    pub data: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+}
+
+impl<T> Paginated<T> {
+    pub fn new(data: Vec<T>, pagination: Pagination, total: u64) -> Self {
+        Paginated {
+            data,
+            page: pagination.page,
+            per_page: pagination.per_page,
+            total,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Paginated<T> {
+    fn into_response(self) -> Response {
+        (OK, Json(self)).into_response()
+    }
+}
+
+/// Nesting depth allowed by `QsQuery<T>`.
+#[cfg(feature = "qs")]
+const QS_MAX_DEPTH: usize = 5;
+
+#[cfg(feature = "qs")]
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for QsQuery<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let params = serde_qs::Config::new(QS_MAX_DEPTH, false)
+            .deserialize_str::<T>(query)
+            .map_err(|err| (T::config().json_status, Json(T::qs_error(err.to_string()))))?;
+
+        if let Err(err) = params.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(QsQuery(params))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Path<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::extract::Path(params) =
+            axum::extract::Path::<T>::from_request_parts(parts, state)
+                .await
+                .map_err(|rejection| (T::config().json_status, Json(T::path_error(rejection))))?;
+
+        if let Err(err) = params.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(Path(params))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Header<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let mut pairs = Vec::new();
+        for name in parts.headers.keys() {
+            // Values that aren't visible ASCII can't be represented as strings
+            let values = parts
+                .headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .collect::<Vec<_>>();
+
+            if !values.is_empty() {
+                pairs.push((name.as_str().replace('-', "_"), values.join(", ")));
+            }
+        }
+
+        // Round-trip through the urlencoded format to get its string to scalar coercion
+        let encoded = serde_urlencoded::to_string(&pairs).unwrap_or_default();
+        let headers = serde_urlencoded::from_str::<T>(&encoded)
+            .map_err(|err| (T::config().json_status, Json(T::header_error(err))))?;
+
+        if let Err(err) = headers.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(Header(headers))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, B, Q> FromRequest<S> for BodyQuery<B, Q>
+where
+    S: Send + Sync,
+    B: Send + Sync + 'static + DeserializeOwned + Validate + BodyError,
+    Q: Send + Sync + DeserializeOwned + Validate + BodyError,
+    Q::Error: Send,
+{
+    type Rejection = (StatusCode, Json<serde_json::Value>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let (mut parts, body) = req.into_parts();
+        let query = Query::<Q>::from_request_parts(&mut parts, state).await;
+        let body = Body::<B>::from_request(Request::from_parts(parts, body), state)
+            .await
+            .map_err(BodyRejection::into_parts);
+
+        match (body, query) {
+            (Ok(Body(body)), Ok(Query(query))) => Ok(BodyQuery(body, query)),
+            (Err((status, Json(body))), Ok(_)) => {
+                Err((status, Json(B::merge::<Q::Error>(Some(body), None))))
+            }
+            (Ok(_), Err((status, Json(query)))) => Err((status, Json(B::merge(None, Some(query))))),
+            (Err((status, Json(body))), Err((_, Json(query)))) => {
+                Err((status, Json(B::merge(Some(body), Some(query)))))
+            }
+        }
+    }
+}
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+#[async_trait::async_trait]
+impl<S, T> FromRequest<S> for Form<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + DeserializeOwned + Validate + BodyError,
+{
+    type Rejection = (StatusCode, Json<T::Error>);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_content_type(req.headers(), FORM_CONTENT_TYPE) {
+            let error = T::content_type_error(FORM_CONTENT_TYPE);
+            return Err((UNSUPPORTED_MEDIA_TYPE, Json(error)));
+        }
+
+        let axum::Form(body) = axum::Form::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| (T::config().json_status, Json(T::form_error(rejection))))?;
+
+        if let Err(err) = body.validate() {
+            return Err(validation_rejection::<T>(err));
+        };
+
+        Ok(Form(body))
+    }
+}
+
+/// Validated extractor usable in `All`, `KEY` names its errors in the merged rejection.
+#[async_trait::async_trait]
+pub trait AllPart<S>: Sized {
+    const KEY: &'static str;
+
+    async fn extract(req: Request, state: &S) -> Result<Self, Rejected>;
+}
+
+/// A rejection collected by `All`.
+#[derive(Debug)]
+pub struct Rejected {
+    pub key: &'static str,
+    pub status: StatusCode,
+    pub error: serde_json::Value,
+}
+
+impl Rejected {
+    fn new<E: Serialize>(key: &'static str, (status, Json(error)): (StatusCode, Json<E>)) -> Self {
+        let error = serde_json::to_value(error).unwrap_or_default();
+        Rejected { key, status, error }
+    }
+}
+
+/// How `All` turns the collected rejections into one response.
+pub trait MergeRejections {
+    fn merge(rejections: Vec<Rejected>) -> Response;
+}
+
+/// Default `MergeRejections`, an object of errors keyed by `AllPart::KEY` sent with the
+/// status of the first rejection.
+pub struct KeyedRejections;
+
+impl MergeRejections for KeyedRejections {
+    fn merge(rejections: Vec<Rejected>) -> Response {
+        let status = rejections
+            .first()
+            .map_or(BAD_REQUEST, |rejected| rejected.status);
+        let errors = rejections
+            .into_iter()
+            .map(|rejected| (string!(rejected.key), rejected.error))
+            .collect::<serde_json::Map<_, _>>();
+
+        (status, Json(errors)).into_response()
+    }
+}
+
+macro_rules! impl_all_part {
+    (request $ty:ident, $key:literal) => {
+        #[async_trait::async_trait]
+        impl<S, T> AllPart<S> for $ty<T>
+        where
+            S: Send + Sync,
+            T: Send + Sync + 'static + DeserializeOwned + Validate + BodyError,
+        {
+            const KEY: &'static str = $key;
+
+            async fn extract(req: Request, state: &S) -> Result<Self, Rejected> {
+                <$ty<T> as FromRequest<S>>::from_request(req, state)
+                    .await
+                    .map_err(|rejection| Rejected::new::<T::Error>($key, rejection.into()))
+            }
+        }
+    };
+    (parts $ty:ident, $key:literal) => {
+        #[async_trait::async_trait]
+        impl<S, T> AllPart<S> for $ty<T>
+        where
+            S: Send + Sync,
+            T: Send + Sync + DeserializeOwned + Validate + BodyError,
+        {
+            const KEY: &'static str = $key;
+
+            async fn extract(req: Request, state: &S) -> Result<Self, Rejected> {
+                let (mut parts, _) = req.into_parts();
+                <$ty<T> as FromRequestParts<S>>::from_request_parts(&mut parts, state)
+                    .await
+                    .map_err(|rejection| Rejected::new($key, rejection))
+            }
+        }
+    };
+}
+
+impl_all_part!(request Body, "body");
+
+impl_all_part!(request Form, "form");
+
+impl_all_part!(parts Query, "query");
+
+impl_all_part!(parts Path, "path");
+
+impl_all_part!(parts Header, "headers");
+
+/// Copy of the buffered request for one extractor of `All`.
+fn replay(parts: &Parts, body: &Bytes) -> Request {
+    let mut req = Request::new(axum::body::Body::from(body.clone()));
+    *req.method_mut() = parts.method.clone();
+    *req.uri_mut() = parts.uri.clone();
+    *req.version_mut() = parts.version;
+    *req.headers_mut() = parts.headers.clone();
+    *req.extensions_mut() = parts.extensions.clone();
+    req
+}
+
+macro_rules! impl_all {
+    ($($ty:ident),+) => {
+        #[async_trait::async_trait]
+        impl<S, M, $($ty),+> FromRequest<S> for All<($($ty,)+), M>
+        where
+            S: Send + Sync,
+            M: MergeRejections,
+            $($ty: AllPart<S> + Send,)+
+        {
+            type Rejection = Response;
+
+            #[allow(non_snake_case)]
+            async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+                let (parts, body) = req.into_parts();
+                let mut head = replay(&parts, &Bytes::new());
+                *head.body_mut() = body;
+
+                let body = Bytes::from_request(head, state)
+                    .await
+                    .map_err(IntoResponse::into_response)?;
+
+                let mut rejections = Vec::new();
+                $(let $ty = $ty::extract(replay(&parts, &body), state)
+                    .await
+                    .map_err(|rejected| rejections.push(rejected))
+                    .ok();)+
+
+                match ($($ty,)+) {
+                    ($(Some($ty),)+) => Ok(All(($($ty,)+), PhantomData)),
+                    _ => Err(M::merge(rejections)),
+                }
+            }
+        }
+    };
+}
+
+impl_all!(A, B);
+
+impl_all!(A, B, C);
+
+impl_all!(A, B, C, D);
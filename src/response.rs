@@ -0,0 +1,424 @@
+//! Response helpers: status shorthands, `Respond<T>`, redirects and conditional requests.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Bytes,
+    extract::FromRequestParts,
+    http::{
+        header::{HeaderName, CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH, LOCATION, RETRY_AFTER},
+        request::Parts,
+        HeaderMap, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{Stream, StreamExt};
+use serde::Serialize;
+use std_plus::f;
+
+use crate::{
+    ACCEPTED, CREATED, INTERNAL_SERVER_ERROR, NOT_MODIFIED, NO_CONTENT, OK, PERMANENT_REDIRECT,
+    PRECONDITION_FAILED, SEE_OTHER, TEMPORARY_REDIRECT, TOO_MANY_REQUESTS,
+};
+
+pub fn json_ok<T: Serialize>(body: T) -> (StatusCode, Json<T>) {
+    (OK, Json(body))
+}
+
+pub fn created<T: Serialize>(body: T) -> (StatusCode, Json<T>) {
+    (CREATED, Json(body))
+}
+
+pub fn accepted<T: Serialize>(body: T) -> (StatusCode, Json<T>) {
+    (ACCEPTED, Json(body))
+}
+
+pub fn reject<E: Serialize>(status: StatusCode, err: E) -> (StatusCode, Json<E>) {
+    (status, Json(err))
+}
+
+pub fn no_content() -> StatusCode {
+    NO_CONTENT
+}
+
+/// Default success status of a response DTO, used by `Respond<T>`. `#[derive(ResponseStatus)]`
+/// (or an empty `impl ResponseStatus for T {}`) keeps `OK`, std collections and scalars are
+/// `OK` too.
+///
+/// ```
+/// use axum_plus::{Respond, ResponseStatus, CREATED, OK};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize, ResponseStatus)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[derive(Serialize, ResponseStatus)]
+/// #[response_status(CREATED)]
+/// struct NewUser {
+///     id: u64,
+/// }
+///
+/// assert_eq!(OK, User::STATUS);
+/// assert_eq!(CREATED, NewUser::STATUS);
+///
+/// async fn create() -> Respond<NewUser> {
+///     Respond(NewUser { id: 1 })
+/// }
+/// ```
+pub trait ResponseStatus {
+    const STATUS: StatusCode = OK;
+}
+
+macro_rules! impl_response_status {
+    ($($ty:ty),* $(,)?) => {
+        $(impl ResponseStatus for $ty {})*
+    };
+}
+
+impl_response_status!(
+    serde_json::Value,
+    String,
+    &'static str,
+    bool,
+    char,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    f32,
+    f64,
+);
+
+impl<T> ResponseStatus for Vec<T> {}
+
+impl<T> ResponseStatus for Option<T> {}
+
+impl<K, V, S> ResponseStatus for HashMap<K, V, S> {}
+
+impl<K, V> ResponseStatus for BTreeMap<K, V> {}
+
+/// JSON response sent with `T::STATUS`, e.g. `CREATED` for a created resource.
+pub struct Respond<T>(pub T);
+
+impl<T: Serialize + ResponseStatus> IntoResponse for Respond<T> {
+    fn into_response(self) -> Response {
+        (T::STATUS, Json(self.0)).into_response()
+    }
+}
+
+/// `CREATED` JSON response, the optional second field is sent as the `Location` header.
+pub struct Created<T>(pub T, pub Option<String>);
+
+impl<T: Serialize> IntoResponse for Created<T> {
+    fn into_response(self) -> Response {
+        let Created(body, location) = self;
+        let mut res = (CREATED, Json(body)).into_response();
+
+        if let Some(location) = location.and_then(location_header) {
+            res.headers_mut().insert(LOCATION, location);
+        }
+
+        res
+    }
+}
+
+/// Redirect response with the `Location` header, an invalid location is logged and sent as
+/// `INTERNAL_SERVER_ERROR`.
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    status: StatusCode,
+    location: String,
+}
+
+impl Redirect {
+    /// `PERMANENT_REDIRECT`, the method and body are kept.
+    pub fn permanent(uri: impl Into<String>) -> Self {
+        Redirect {
+            status: PERMANENT_REDIRECT,
+            location: uri.into(),
+        }
+    }
+
+    /// `TEMPORARY_REDIRECT`, the method and body are kept.
+    pub fn temporary(uri: impl Into<String>) -> Self {
+        Redirect {
+            status: TEMPORARY_REDIRECT,
+            location: uri.into(),
+        }
+    }
+
+    /// `SEE_OTHER`, followed with a `GET`, e.g. after a form submission.
+    pub fn see_other(uri: impl Into<String>) -> Self {
+        Redirect {
+            status: SEE_OTHER,
+            location: uri.into(),
+        }
+    }
+}
+
+impl IntoResponse for Redirect {
+    fn into_response(self) -> Response {
+        match location_header(self.location) {
+            Some(location) => (self.status, [(LOCATION, location)]).into_response(),
+            None => INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
+
+/// `ACCEPTED` response for queued work, `Location` points to where the job status can be
+/// polled and `body` is sent as JSON when set (no body at all otherwise). An invalid status
+/// URL is handled like a `Redirect` location.
+#[derive(Debug, Clone)]
+pub struct Accepted<T = ()> {
+    pub status_url: String,
+    pub body: Option<T>,
+}
+
+impl Accepted {
+    pub fn new(status_url: impl Into<String>) -> Self {
+        Accepted {
+            status_url: status_url.into(),
+            body: None,
+        }
+    }
+}
+
+impl<T> Accepted<T> {
+    pub fn with_body<B>(self, body: B) -> Accepted<B> {
+        Accepted {
+            status_url: self.status_url,
+            body: Some(body),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Accepted<T> {
+    fn into_response(self) -> Response {
+        let Some(location) = location_header(self.status_url) else {
+            return INTERNAL_SERVER_ERROR.into_response();
+        };
+
+        match self.body {
+            Some(body) => (ACCEPTED, [(LOCATION, location)], Json(body)).into_response(),
+            None => (ACCEPTED, [(LOCATION, location)]).into_response(),
+        }
+    }
+}
+
+fn location_header(location: String) -> Option<HeaderValue> {
+    HeaderValue::try_from(location)
+        .inspect_err(|err| tracing::error!("Invalid Location header value: {}", err))
+        .ok()
+}
+
+/// `Retry-After` value, a delay in seconds or an HTTP-date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryAfter {
+    Delay(Duration),
+    Date(SystemTime),
+}
+
+impl RetryAfter {
+    fn header(self) -> HeaderValue {
+        match self {
+            // Rounded up so clients don't come back before the window is over
+            RetryAfter::Delay(delay) => {
+                let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+                HeaderValue::from(seconds)
+            }
+            // `fmt_http_date` panics outside of years 1970..=9999, a past date means retrying
+            // right away and later ones are clamped to the last second of 9999
+            RetryAfter::Date(date) => match date.duration_since(UNIX_EPOCH) {
+                Ok(since) => {
+                    let date = UNIX_EPOCH + since.min(MAX_HTTP_DATE);
+                    HeaderValue::try_from(httpdate::fmt_http_date(date))
+                        .expect("HTTP-date is a valid header value")
+                }
+                Err(_) => HeaderValue::from(0),
+            },
+        }
+    }
+}
+
+/// `Fri, 31 Dec 9999 23:59:59 GMT`, the last HTTP-date `httpdate` can format.
+const MAX_HTTP_DATE: Duration = Duration::from_secs(253_402_300_799);
+
+/// `TOO_MANY_REQUESTS` with the `Retry-After` header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    pub retry_after: RetryAfter,
+}
+
+impl RateLimited {
+    pub fn after(delay: Duration) -> Self {
+        RateLimited {
+            retry_after: RetryAfter::Delay(delay),
+        }
+    }
+
+    pub fn until(date: SystemTime) -> Self {
+        RateLimited {
+            retry_after: RetryAfter::Date(date),
+        }
+    }
+}
+
+impl IntoResponse for RateLimited {
+    fn into_response(self) -> Response {
+        let retry_after = self.retry_after.header();
+        (TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after)]).into_response()
+    }
+}
+
+/// JSON array response written item by item from a stream (e.g. rows of a database cursor)
+/// without collecting it, an empty stream sends `[]`. An item failing to serialize is logged
+/// and aborts the response so clients never mistake a truncated array for a complete one.
+pub struct JsonArrayStream<S>(pub S);
+
+impl<S> IntoResponse for JsonArrayStream<S>
+where
+    S: Stream + Send + 'static,
+    S::Item: Serialize,
+{
+    fn into_response(self) -> Response {
+        use futures_util::{future::ready, stream};
+
+        let items = self.0.enumerate().map(|(index, item)| {
+            let mut chunk = match index {
+                0 => Vec::new(),
+                _ => vec![b','],
+            };
+
+            serde_json::to_writer(&mut chunk, &item).inspect_err(|err| {
+                tracing::error!("Failed to serialize a streamed item: {}", err)
+            })?;
+            Ok::<_, serde_json::Error>(Bytes::from(chunk))
+        });
+
+        let array = stream::once(ready(Ok(Bytes::from_static(b"["))))
+            .chain(items)
+            .chain(stream::once(ready(Ok(Bytes::from_static(b"]")))));
+
+        (
+            [(CONTENT_TYPE, HeaderValue::from_static("application/json"))],
+            axum::body::Body::from_stream(array),
+        )
+            .into_response()
+    }
+}
+
+/// Entity tags of the `If-None-Match` header, empty when it's missing.
+#[derive(Debug, Clone, Default)]
+pub struct IfNoneMatch(pub Vec<String>);
+
+/// Entity tags of the `If-Match` header, empty when it's missing.
+#[derive(Debug, Clone, Default)]
+pub struct IfMatch(pub Vec<String>);
+
+impl IfNoneMatch {
+    /// Weak comparison, `W/"a"` matches `"a"`.
+    pub fn matches(&self, etag: &str) -> bool {
+        let etag = quote_etag(etag);
+        self.0
+            .iter()
+            .any(|tag| tag == "*" || weak_etag(tag) == weak_etag(&etag))
+    }
+}
+
+impl IfMatch {
+    /// Strong comparison, weak tags never match.
+    pub fn matches(&self, etag: &str) -> bool {
+        let etag = quote_etag(etag);
+        self.0
+            .iter()
+            .any(|tag| tag == "*" || (!tag.starts_with("W/") && *tag == etag))
+    }
+
+    /// `PRECONDITION_FAILED` when the header is present and doesn't match the current `etag`,
+    /// meant to guard writes with `?`.
+    pub fn precondition(&self, etag: &str) -> Result<(), StatusCode> {
+        match self.0.is_empty() || self.matches(etag) {
+            true => Ok(()),
+            false => Err(PRECONDITION_FAILED),
+        }
+    }
+}
+
+fn entity_tags(headers: &HeaderMap, name: HeaderName) -> Vec<String> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Quotes bare values so `respond_conditional(.., "v1", ..)` sends `ETag: "v1"`.
+fn quote_etag(etag: &str) -> String {
+    match etag.starts_with('"') || etag.starts_with("W/") {
+        true => etag.to_owned(),
+        false => f!("\"{}\"", etag),
+    }
+}
+
+fn weak_etag(etag: &str) -> &str {
+    etag.strip_prefix("W/").unwrap_or(etag)
+}
+
+#[async_trait::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfNoneMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfNoneMatch(entity_tags(&parts.headers, IF_NONE_MATCH)))
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfMatch(entity_tags(&parts.headers, IF_MATCH)))
+    }
+}
+
+/// `NOT_MODIFIED` when `if_none_match` matches `etag`, the full `body` otherwise, both with
+/// the `ETag` header set.
+pub fn respond_conditional<T: IntoResponse>(
+    if_none_match: &IfNoneMatch,
+    etag: &str,
+    body: T,
+) -> Response {
+    let mut res = match if_none_match.matches(etag) {
+        true => NOT_MODIFIED.into_response(),
+        false => body.into_response(),
+    };
+
+    match HeaderValue::try_from(quote_etag(etag)) {
+        Ok(value) => {
+            res.headers_mut().insert(ETAG, value);
+        }
+        Err(err) => tracing::error!("Invalid ETag header value: {}", err),
+    }
+
+    res
+}
@@ -0,0 +1,1070 @@
+//! `Static<T>` and the layers inserting `&'static` values into request extensions.
+
+use std::{
+    any::type_name,
+    collections::HashMap,
+    convert::Infallible,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+    task::{Context, Poll},
+};
+
+use axum::{
+    extract::{FromRef, FromRequestParts, Request},
+    http::{request::Parts, Extensions, StatusCode},
+    response::{IntoResponse, Response},
+    Router,
+};
+use std_plus::new;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::RejectedBy;
+
+#[macro_export]
+macro_rules! static_service {
+    ($data:expr) => {{
+        $crate::StaticLayer::new($data)
+    }};
+    ($($data:expr),+ $(,)?) => {{
+        $crate::StaticsLayer::new(($($data,)+))
+    }};
+}
+
+/// `StaticLayer` that also inserts the value for axum's `Extension<T>` and the reference for
+/// `Extension<&'static T>`, for middleware reading extensions. `Extension<T>` is owned, so
+/// every request gets a `T::clone()` of the static: keep `T` cheap to clone (an `Arc` or a
+/// few `Copy` fields) or extract `Static<T>` and `Extension<&'static T>` instead.
+#[macro_export]
+macro_rules! static_and_extension_service {
+    ($data:expr $(,)?) => {{
+        $crate::StaticExtensionLayer::new($data)
+    }};
+}
+
+#[derive(new)]
+pub struct StaticExtensionLayer<T: 'static> {
+    ext: &'static T,
+}
+
+impl<T> Clone for StaticExtensionLayer<T> {
+    fn clone(&self) -> Self {
+        StaticExtensionLayer::new(self.ext)
+    }
+}
+
+impl<S, T: 'static> Layer<S> for StaticExtensionLayer<T> {
+    type Service = AddStaticExtension<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddStaticExtension::new(inner, self.ext)
+    }
+}
+
+#[derive(new)]
+pub struct AddStaticExtension<S, T: 'static> {
+    inner: S,
+    ext: &'static T,
+}
+
+impl<S: Clone, T> Clone for AddStaticExtension<S, T> {
+    fn clone(&self) -> Self {
+        AddStaticExtension::new(self.inner.clone(), self.ext)
+    }
+}
+
+impl<ReqBody, S, T> Service<Request<ReqBody>> for AddStaticExtension<S, T>
+where
+    S: Service<Request<ReqBody>>,
+    T: Clone + Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        req.extensions_mut().insert(Static::new(self.ext));
+        req.extensions_mut().insert(self.ext);
+        req.extensions_mut().insert(self.ext.clone());
+        self.inner.call(req)
+    }
+}
+
+#[derive(new)]
+pub struct AddStatic<S, T: ?Sized + 'static> {
+    inner: S,
+    ext: &'static T,
+}
+
+impl<S: Clone, T: ?Sized> Clone for AddStatic<S, T> {
+    fn clone(&self) -> Self {
+        AddStatic::new(self.inner.clone(), self.ext)
+    }
+}
+
+/// Inserts a `Static<T>` into the extensions of every request it forwards, so anything inside
+/// it in a tower stack sees the static, `map_request` closures included, while services
+/// wrapping it don't.
+#[derive(new)]
+pub struct StaticLayer<T: ?Sized + 'static> {
+    ext: &'static T,
+}
+
+impl<T: ?Sized> Clone for StaticLayer<T> {
+    fn clone(&self) -> Self {
+        StaticLayer::new(self.ext)
+    }
+}
+
+impl<S, T> Layer<S> for StaticLayer<T>
+where
+    T: ?Sized + 'static,
+{
+    type Service = AddStatic<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddStatic::new(inner, self.ext)
+    }
+}
+
+impl StaticLayer<()> {
+    /// Composes several statics into one layer.
+    ///
+    /// ```
+    /// use axum::{routing::get, Router};
+    /// use axum_plus::{Static, StaticLayer};
+    ///
+    /// struct Config {
+    ///     name: &'static str,
+    /// }
+    ///
+    /// static CONFIG: Config = Config { name: "West" };
+    /// static GREETING: &str = "Hello";
+    ///
+    /// async fn handler(config: Static<Config>, greeting: Static<&'static str>) -> String {
+    ///     format!("{} {}", *greeting, config.name)
+    /// }
+    ///
+    /// let statics = StaticLayer::builder().add(&CONFIG).add(&GREETING).build();
+    ///
+    /// // `Router::layer` attaches them to every route, `MethodRouter::layer` to a single one
+    /// let app: Router = Router::new().route("/", get(handler)).layer(statics.clone());
+    /// let app: Router = Router::new().route("/", get(handler).layer(statics));
+    /// ```
+    pub fn builder() -> StaticLayerBuilder<()> {
+        StaticLayerBuilder(())
+    }
+}
+
+pub struct StaticLayerBuilder<L>(L);
+
+impl<L: Statics> StaticLayerBuilder<L> {
+    pub fn add<T>(self, value: &'static T) -> StaticLayerBuilder<StaticChain<L, T>>
+    where
+        T: ?Sized + Sync + 'static,
+    {
+        StaticLayerBuilder(StaticChain {
+            rest: self.0,
+            value,
+        })
+    }
+
+    pub fn build(self) -> StaticsLayer<L> {
+        StaticsLayer::new(self.0)
+    }
+}
+
+/// Statics collected by `StaticLayerBuilder`, one `add` per link.
+pub struct StaticChain<L, T: ?Sized + 'static> {
+    rest: L,
+    value: &'static T,
+}
+
+impl<L: Clone, T: ?Sized> Clone for StaticChain<L, T> {
+    fn clone(&self) -> Self {
+        StaticChain {
+            rest: self.rest.clone(),
+            value: self.value,
+        }
+    }
+}
+
+impl Statics for () {
+    fn insert_into(&self, _: &mut Extensions) {}
+}
+
+impl<L, T> Statics for StaticChain<L, T>
+where
+    L: Statics,
+    T: ?Sized + Sync + 'static,
+{
+    fn insert_into(&self, extensions: &mut Extensions) {
+        self.rest.insert_into(extensions);
+        extensions.insert(Static::new(self.value));
+    }
+}
+
+/// Chainable `StaticLayer` for `axum::Router`.
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use axum_plus::{RouterStaticExt, Static};
+///
+/// #[derive(Clone)]
+/// struct Config {
+///     name: &'static str,
+/// }
+///
+/// #[derive(Clone)]
+/// struct Greeting {
+///     text: &'static str,
+/// }
+///
+/// async fn handler(config: Static<Config>, greeting: Static<Greeting>) -> String {
+///     format!("{} {}", greeting.text, config.name)
+/// }
+///
+/// static CONFIG: Config = Config { name: "West" };
+/// static GREETING: Greeting = Greeting { text: "Hello" };
+///
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .with_static(&CONFIG)
+///     .with_static(&GREETING);
+/// ```
+pub trait RouterStaticExt {
+    fn with_static<T>(self, value: &'static T) -> Self
+    where
+        T: ?Sized + Sync + 'static;
+}
+
+impl<S> RouterStaticExt for Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn with_static<T>(self, value: &'static T) -> Self
+    where
+        T: ?Sized + Sync + 'static,
+    {
+        self.layer(StaticLayer::new(value))
+    }
+}
+
+/// Extracts the `&'static T` added by `StaticLayer<T>`. `T` may be unsized, e.g.
+/// `Static<str>` or `Static<[u8]>` for a template or key loaded at startup.
+///
+/// Any `T: Sync` works: the one reference is shared by every task serving a request, which is
+/// what `Sync` guarantees is sound, and copying `Static<T>` only copies that reference so `T`
+/// doesn't need to be `Clone` (or `Send`).
+///
+/// `T` can be a trait object so handlers depend on an interface, e.g. `Static<dyn Repository>`
+/// added with `StaticLayer::<dyn Repository>::new(&REPOSITORY)`. The trait needs `Sync` as a
+/// supertrait (or use `Static<dyn Repository + Sync>`) and the implementation must live for
+/// `'static`, such as a `static` item or a leaked `Box`.
+///
+/// With several `StaticLayer<T>` of the same `T` the innermost one (closest to the handler)
+/// wins, e.g. a layer on a `MethodRouter` overrides one on the whole `Router`. Each layer
+/// inserts its value on the way in, replacing the one of the layers around it.
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use axum_plus::{RouterStaticExt, Static};
+///
+/// struct Config {
+///     name: &'static str,
+/// }
+///
+/// static CONFIG: Config = Config { name: "West" };
+///
+/// async fn handler(config: Static<Config>) -> String {
+///     // Through `Deref`, `get` or `AsRef`, all borrowing the same `&'static Config`
+///     let name = config.name;
+///     let config: &'static Config = config.get();
+///     format!("{} {}", name, config.name)
+/// }
+///
+/// fn name<C: AsRef<Config>>(config: C) -> &'static str {
+///     config.as_ref().name
+/// }
+///
+/// assert_eq!("West", name(Static::new(&CONFIG)));
+///
+/// let app: Router = Router::new().route("/", get(handler)).with_static(&CONFIG);
+/// ```
+#[derive(new)]
+pub struct Static<T: ?Sized + 'static>(pub &'static T);
+
+impl<T: ?Sized> Static<T> {
+    pub fn get(&self) -> &'static T {
+        self.0
+    }
+}
+
+impl<T: ?Sized> AsRef<T> for Static<T> {
+    fn as_ref(&self) -> &T {
+        self.0
+    }
+}
+
+// Only the reference is copied, `T` itself doesn't need to be `Clone`
+impl<T: ?Sized> Clone for Static<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for Static<T> {}
+
+impl<T: ?Sized> std::ops::Deref for Static<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<ReqBody, S, T> Service<Request<ReqBody>> for AddStatic<S, T>
+where
+    S: Service<Request<ReqBody>>,
+    T: ?Sized + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        req.extensions_mut().insert(Static::new(self.ext));
+        self.inner.call(req)
+    }
+}
+
+/// Tuple of `&'static` values inserted at once by `StaticsLayer`, each extractable
+/// individually through `Static<T>`.
+pub trait Statics: Clone {
+    fn insert_into(&self, extensions: &mut Extensions);
+}
+
+macro_rules! impl_statics {
+    ($($ty:ident),+) => {
+        impl<$($ty),+> Statics for ($(&'static $ty,)+)
+        where
+            $($ty: ?Sized + Sync + 'static,)+
+        {
+            #[allow(non_snake_case)]
+            fn insert_into(&self, extensions: &mut Extensions) {
+                let ($($ty,)+) = *self;
+                $(extensions.insert(Static::new($ty));)+
+            }
+        }
+    };
+}
+
+impl_statics!(A, B);
+
+impl_statics!(A, B, C);
+
+impl_statics!(A, B, C, D);
+
+impl_statics!(A, B, C, D, E);
+
+impl_statics!(A, B, C, D, E, F);
+
+impl_statics!(A, B, C, D, E, F, G);
+
+impl_statics!(A, B, C, D, E, F, G, H);
+
+/// Startup builder for the app-wide statics, values can be built from the ones added before
+/// them and are leaked together by `build`.
+///
+/// Each value is boxed on `add` (one allocation per value, as a `dyn Any` so the set can hold
+/// any type) and `build` leaks those boxes and the list of entries, never to be freed. That's
+/// the price of handing out `&'static` references: build one set at startup, not per request
+/// or per test run in a loop, or the leaked memory grows with every call.
+///
+/// ```
+/// use axum::{routing::get, Router};
+/// use axum_plus::{Static, StaticSet};
+///
+/// struct Config {
+///     url: String,
+/// }
+///
+/// struct Pool {
+///     url: String,
+/// }
+///
+/// async fn handler(pool: Static<Pool>, config: Static<Config>) -> String {
+///     format!("{} {}", pool.url, config.url)
+/// }
+///
+/// let mut set = StaticSet::new();
+/// let config = set.add(Config { url: "postgres://db".into() });
+/// let pool = set.add(Pool { url: set.get(config).unwrap().url.clone() });
+///
+/// let statics = set.build();
+/// assert_eq!("postgres://db", statics.get(pool).unwrap().url);
+///
+/// // Handles only resolve in the set that made them
+/// assert!(StaticSet::new().get(config).is_none());
+///
+/// let app: Router = Router::new().route("/", get(handler)).layer(statics.layer());
+/// ```
+pub struct StaticSet {
+    id: usize,
+    entries: Vec<StaticEntry>,
+}
+
+/// Tells the `StaticSet`s apart so a handle never resolves in another set.
+static STATIC_SET_IDS: AtomicUsize = AtomicUsize::new(0);
+
+impl Default for StaticSet {
+    fn default() -> Self {
+        StaticSet {
+            id: STATIC_SET_IDS.fetch_add(1, Ordering::Relaxed),
+            entries: Vec::new(),
+        }
+    }
+}
+
+struct StaticEntry {
+    value: Box<dyn std::any::Any + Send + Sync>,
+    insert: fn(&'static (dyn std::any::Any + Send + Sync), &mut Extensions),
+}
+
+/// Typed index of a value added to a `StaticSet`, only valid for that set.
+pub struct StaticHandle<T> {
+    set: usize,
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for StaticHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for StaticHandle<T> {}
+
+impl StaticSet {
+    pub fn new() -> Self {
+        StaticSet::default()
+    }
+
+    pub fn add<T: Send + Sync + 'static>(&mut self, value: T) -> StaticHandle<T> {
+        self.entries.push(StaticEntry {
+            value: Box::new(value),
+            insert: |value, extensions| {
+                if let Some(value) = value.downcast_ref::<T>() {
+                    extensions.insert(Static::new(value));
+                }
+            },
+        });
+
+        StaticHandle {
+            set: self.id,
+            index: self.entries.len() - 1,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Borrows a value added earlier, to build the ones depending on it. `None` for a handle
+    /// of another set.
+    pub fn get<T: 'static>(&self, handle: StaticHandle<T>) -> Option<&T> {
+        static_entry(self.id, &self.entries, handle)
+    }
+
+    /// Leaks the values and their boxes, they live for the rest of the program.
+    pub fn build(self) -> StaticValues {
+        StaticValues {
+            id: self.id,
+            entries: Box::leak(self.entries.into_boxed_slice()),
+        }
+    }
+}
+
+fn static_entry<T: 'static>(
+    id: usize,
+    entries: &[StaticEntry],
+    handle: StaticHandle<T>,
+) -> Option<&T> {
+    if handle.set != id {
+        return None;
+    }
+
+    entries.get(handle.index)?.value.downcast_ref()
+}
+
+/// The leaked values of a `StaticSet`.
+#[derive(Clone, Copy)]
+pub struct StaticValues {
+    id: usize,
+    entries: &'static [StaticEntry],
+}
+
+impl StaticValues {
+    /// `None` for a handle of another set.
+    pub fn get<T: 'static>(&self, handle: StaticHandle<T>) -> Option<&'static T> {
+        static_entry(self.id, self.entries, handle)
+    }
+
+    /// Layer inserting every value, each extractable through `Static<T>`.
+    pub fn layer(&self) -> StaticsLayer<StaticValues> {
+        StaticsLayer::new(*self)
+    }
+}
+
+impl Statics for StaticValues {
+    fn insert_into(&self, extensions: &mut Extensions) {
+        for entry in self.entries {
+            (entry.insert)(&*entry.value, extensions);
+        }
+    }
+}
+
+#[derive(new, Clone)]
+pub struct AddStatics<S, L> {
+    inner: S,
+    exts: L,
+}
+
+#[derive(new, Clone)]
+pub struct StaticsLayer<L> {
+    exts: L,
+}
+
+impl<S, L> Layer<S> for StaticsLayer<L>
+where
+    L: Statics,
+{
+    type Service = AddStatics<S, L>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddStatics::new(inner, self.exts.clone())
+    }
+}
+
+impl<ReqBody, S, L> Service<Request<ReqBody>> for AddStatics<S, L>
+where
+    S: Service<Request<ReqBody>>,
+    L: Statics,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        self.exts.insert_into(req.extensions_mut());
+        self.inner.call(req)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Static<T>
+where
+    T: ?Sized + Sync + 'static,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        // Copies the `&'static T` only, `T` is never cloned
+        if let Some(value) = parts.extensions.get::<Static<T>>().copied() {
+            return Ok(value);
+        }
+
+        tracing::error!(
+            "Failed to  extract {}, is it added via StaticLayer",
+            type_name::<Static<T>>()
+        );
+
+        let hook = parts.extensions.get::<MissingStaticHook>();
+        if let Some(rejection) = hook.and_then(|MissingStaticHook(hook)| hook(type_name::<T>())) {
+            return Err(rejection);
+        }
+
+        let (status, message) = parts.extensions.get::<StaticConfig<T>>().map_or(
+            (StatusCode::INTERNAL_SERVER_ERROR, "Unknown error occurred!"),
+            |config| (config.status, config.message),
+        );
+        Err(ExtensionRejection::new(
+            status,
+            message,
+            type_name::<Static<T>>(),
+        ))
+    }
+}
+
+/// Rejection of the extension backed extractors (`Static<T>`, `Dynamic<T>`, ...), the response
+/// carries a `RejectedBy` extension naming the extractor.
+#[derive(new, Debug, Clone, Copy)]
+pub struct ExtensionRejection {
+    pub status: StatusCode,
+    pub message: &'static str,
+    pub extractor: &'static str,
+}
+
+/// The rejection of these extractors before `ExtensionRejection`, keeps handlers returning
+/// `Result<_, (StatusCode, &'static str)>` compiling with `?`.
+impl From<ExtensionRejection> for (StatusCode, &'static str) {
+    fn from(rejection: ExtensionRejection) -> Self {
+        (rejection.status, rejection.message)
+    }
+}
+
+impl IntoResponse for ExtensionRejection {
+    fn into_response(self) -> Response {
+        let mut res = (self.status, self.message).into_response();
+        res.extensions_mut().insert(RejectedBy(self.extractor));
+        res
+    }
+}
+
+/// Hot reloadable static, a `&'static ArcSwap<T>` added with `SwappableLayer` that handlers
+/// `load` lock-free while a background task `store`s new values.
+#[cfg(feature = "arc-swap")]
+pub struct Swappable<T: 'static>(pub &'static arc_swap::ArcSwap<T>);
+
+#[cfg(feature = "arc-swap")]
+pub type SwappableLayer<T> = StaticLayer<arc_swap::ArcSwap<T>>;
+
+#[cfg(feature = "arc-swap")]
+impl<T> Swappable<T> {
+    /// Cheap snapshot of the current value, don't hold it across long awaits.
+    pub fn load(&self) -> arc_swap::Guard<Arc<T>> {
+        self.0.load()
+    }
+
+    pub fn load_full(&self) -> Arc<T> {
+        self.0.load_full()
+    }
+
+    pub fn store(&self, value: impl Into<Arc<T>>) {
+        self.0.store(value.into())
+    }
+}
+
+#[cfg(feature = "arc-swap")]
+impl<T> Clone for Swappable<T> {
+    fn clone(&self) -> Self {
+        Swappable(self.0)
+    }
+}
+
+#[cfg(feature = "arc-swap")]
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Swappable<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Static(swap) = Static::<arc_swap::ArcSwap<T>>::from_request_parts(parts, state).await?;
+        Ok(Swappable(swap))
+    }
+}
+
+/// `Static<T>` taken from the router state through axum's `FromRef` instead of the request
+/// extensions, for apps that already keep everything in an `AppState`.
+///
+/// Compared to `StaticLayer` nothing is inserted per request and a missing value is a compile
+/// error rather than a 500, but the value is fixed for the whole router and can't be scoped
+/// to a sub-router by layering.
+///
+/// ```
+/// use axum::{extract::FromRef, routing::get, Router};
+/// use axum_plus::{Static, StaticState};
+///
+/// struct Config {
+///     name: &'static str,
+/// }
+///
+/// #[derive(Clone)]
+/// struct AppState {
+///     config: &'static Config,
+/// }
+///
+/// impl FromRef<AppState> for Static<Config> {
+///     fn from_ref(state: &AppState) -> Self {
+///         Static(state.config)
+///     }
+/// }
+///
+/// async fn handler(StaticState(config): StaticState<Config>) -> &'static str {
+///     config.name
+/// }
+///
+/// static CONFIG: Config = Config { name: "West" };
+///
+/// let app: Router = Router::new()
+///     .route("/", get(handler))
+///     .with_state(AppState { config: &CONFIG });
+/// ```
+pub struct StaticState<T: ?Sized + 'static>(pub &'static T);
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for StaticState<T>
+where
+    S: Send + Sync,
+    T: ?Sized + 'static,
+    Static<T>: FromRef<S>,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(_: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Static(value) = Static::from_ref(state);
+        Ok(StaticState(value))
+    }
+}
+
+/// Called by every `Static<T>` (and the extractors built on it) finding no `StaticLayer<T>`,
+/// with the type name of `T`, e.g. to count the failure in a metric. Returning a rejection
+/// replaces the `StaticConfig` or default one, the `tracing` error is emitted either way. Add
+/// it to the request extensions, e.g. `axum::Extension(MissingStaticHook(on_missing))`.
+#[derive(Clone, Copy)]
+pub struct MissingStaticHook(pub fn(&'static str) -> Option<ExtensionRejection>);
+
+/// Overrides the rejection of `Static<T>` when no `StaticLayer<T>` was added, e.g.
+/// `axum::Extension(StaticConfig::<Pool>::new(SERVICE_UNAVAILABLE, "Not ready yet"))`.
+pub struct StaticConfig<T: ?Sized> {
+    status: StatusCode,
+    message: &'static str,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized> StaticConfig<T> {
+    pub fn new(status: StatusCode, message: &'static str) -> Self {
+        StaticConfig {
+            status,
+            message,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for StaticConfig<T> {
+    fn clone(&self) -> Self {
+        StaticConfig::new(self.status, self.message)
+    }
+}
+
+macro_rules! define_static_tuple {
+    ($name:ident, $($ty:ident),+) => {
+        /// Extracts several statics at once, rejecting like `Static<T>` on the first missing one.
+        pub struct $name<$($ty: ?Sized + 'static),+>($(pub &'static $ty),+);
+
+        #[async_trait::async_trait]
+        impl<S, $($ty),+> FromRequestParts<S> for $name<$($ty),+>
+        where
+            S: Send + Sync,
+            $($ty: ?Sized + Sync + 'static,)+
+        {
+            type Rejection = ExtensionRejection;
+
+            #[allow(non_snake_case)]
+            async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+                $(let Static($ty) = Static::<$ty>::from_request_parts(parts, state).await?;)+
+                Ok($name($($ty),+))
+            }
+        }
+    };
+}
+
+define_static_tuple!(Static2, A, B);
+
+define_static_tuple!(Static3, A, B, C);
+
+/// Mutable static state, a `&'static RwLock<T>` added with `StaticLayer` and extracted like
+/// `Static<RwLock<T>>`.
+///
+/// A handler panicking while holding the write guard poisons the lock, `read` and `write` then
+/// reject with `INTERNAL_SERVER_ERROR` for every later request instead of handing out state
+/// that may be half updated. Call `clear_poison` on the lock once it's known to be consistent.
+#[derive(new)]
+pub struct StaticLocked<T: 'static>(pub &'static RwLock<T>);
+
+impl<T> Clone for StaticLocked<T> {
+    fn clone(&self) -> Self {
+        StaticLocked(self.0)
+    }
+}
+
+impl<T> StaticLocked<T> {
+    pub fn read(&self) -> Result<RwLockReadGuard<'static, T>, ExtensionRejection> {
+        self.0.read().map_err(|_| poisoned::<T>())
+    }
+
+    pub fn write(&self) -> Result<RwLockWriteGuard<'static, T>, ExtensionRejection> {
+        self.0.write().map_err(|_| poisoned::<T>())
+    }
+}
+
+fn poisoned<T>() -> ExtensionRejection {
+    let extractor = type_name::<StaticLocked<T>>();
+    tracing::error!("Lock of {} is poisoned", extractor);
+    ExtensionRejection::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Unknown error occurred!",
+        extractor,
+    )
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for StaticLocked<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Static(lock) = Static::<RwLock<T>>::from_request_parts(parts, state).await?;
+        Ok(StaticLocked(lock))
+    }
+}
+
+/// Owned copy of the static added by `StaticLayer<T>`, cloned on every request. Handy when
+/// `T` is cheap to clone and needed by value, e.g. moved into a spawned task.
+pub struct StaticCloned<T>(pub T);
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for StaticCloned<T>
+where
+    S: Send + Sync,
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Static(value) = Static::<T>::from_request_parts(parts, state).await?;
+        Ok(StaticCloned(value.clone()))
+    }
+}
+
+/// Like `Static<T>` but yields `None` instead of rejecting when no `StaticLayer<T>` was added.
+#[derive(new)]
+pub struct OptionalStatic<T: ?Sized + 'static>(pub Option<&'static T>);
+
+impl<T: ?Sized> Clone for OptionalStatic<T> {
+    fn clone(&self) -> Self {
+        OptionalStatic(self.0)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for OptionalStatic<T>
+where
+    T: ?Sized + Sync + 'static,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        let value = parts
+            .extensions
+            .get::<Static<T>>()
+            .map(|Static(value)| *value);
+        Ok(OptionalStatic(value))
+    }
+}
+
+#[macro_export]
+macro_rules! static_registry_service {
+    ($($key:expr => $data:expr),+ $(,)?) => {{
+        let registry = $crate::StaticRegistry::new()$(.with($key, $data))+;
+        $crate::StaticLayer::new(::std::boxed::Box::leak(::std::boxed::Box::new(registry)))
+    }};
+}
+
+/// Several `&'static T` told apart by key, e.g. per tenant configs. Installed with
+/// `static_registry_service!` and extracted as `Static<Registry<T>>`.
+pub struct StaticRegistry<T: 'static> {
+    entries: HashMap<&'static str, &'static T>,
+}
+
+pub type Registry<T> = StaticRegistry<T>;
+
+impl<T> StaticRegistry<T> {
+    pub fn new() -> Self {
+        StaticRegistry {
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn with(mut self, key: &'static str, value: &'static T) -> Self {
+        self.entries.insert(key, value);
+        self
+    }
+
+    pub fn get(&self, key: &str) -> Option<&'static T> {
+        self.entries.get(key).copied()
+    }
+
+    /// Like `get` but rejects with `NOT_FOUND` for unknown keys, usable with `?` in handlers.
+    pub fn lookup(&self, key: &str) -> Result<&'static T, ExtensionRejection> {
+        self.get(key).ok_or(ExtensionRejection::new(
+            StatusCode::NOT_FOUND,
+            "Unknown registry key!",
+            type_name::<StaticRegistry<T>>(),
+        ))
+    }
+}
+
+impl<T> Default for StaticRegistry<T> {
+    fn default() -> Self {
+        StaticRegistry::new()
+    }
+}
+
+impl<T> Clone for StaticRegistry<T> {
+    fn clone(&self) -> Self {
+        StaticRegistry {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! dynamic_service {
+    ($data:expr) => {{
+        $crate::DynamicLayer::new($data)
+    }};
+}
+
+#[macro_export]
+macro_rules! shared_service {
+    ($data:expr) => {{
+        $crate::SharedLayer::new($data)
+    }};
+}
+
+/// `Arc` backed sharing under the naming of the static trio, these are the `Dynamic` types.
+/// Construct and destructure through `Dynamic(..)` since aliases can't be tuple patterns.
+pub type Shared<T> = Dynamic<T>;
+
+pub type SharedLayer<T> = DynamicLayer<T>;
+
+pub type AddShared<S, T> = AddDynamic<S, T>;
+
+/// `AddStatic` counterpart for state owned at runtime, shared via `Arc` instead of leaked.
+pub struct AddDynamic<S, T> {
+    inner: S,
+    ext: Arc<T>,
+}
+
+impl<S: Clone, T> Clone for AddDynamic<S, T> {
+    fn clone(&self) -> Self {
+        AddDynamic {
+            inner: self.inner.clone(),
+            ext: self.ext.clone(),
+        }
+    }
+}
+
+pub struct DynamicLayer<T> {
+    ext: Arc<T>,
+}
+
+impl<T> DynamicLayer<T> {
+    pub fn new(ext: impl Into<Arc<T>>) -> Self {
+        DynamicLayer { ext: ext.into() }
+    }
+}
+
+impl<T> Clone for DynamicLayer<T> {
+    fn clone(&self) -> Self {
+        DynamicLayer {
+            ext: self.ext.clone(),
+        }
+    }
+}
+
+impl<S, T> Layer<S> for DynamicLayer<T> {
+    type Service = AddDynamic<S, T>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AddDynamic {
+            inner,
+            ext: self.ext.clone(),
+        }
+    }
+}
+
+pub struct Dynamic<T>(pub Arc<T>);
+
+impl<T> Clone for Dynamic<T> {
+    fn clone(&self) -> Self {
+        Dynamic(self.0.clone())
+    }
+}
+
+impl<T> std::ops::Deref for Dynamic<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<ReqBody, S, T> Service<Request<ReqBody>> for AddDynamic<S, T>
+where
+    S: Service<Request<ReqBody>>,
+    T: Send + Sync + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        req.extensions_mut().insert(Dynamic(self.ext.clone()));
+        self.inner.call(req)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S, T> FromRequestParts<S> for Dynamic<T>
+where
+    T: Send + Sync + 'static,
+{
+    type Rejection = ExtensionRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        if let Some(value) = parts.extensions.get::<Dynamic<T>>().cloned() {
+            return Ok(value);
+        }
+
+        tracing::error!(
+            "Failed to  extract {}, is it added via DynamicLayer",
+            type_name::<Dynamic<T>>()
+        );
+
+        Err(ExtensionRejection::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Unknown error occurred!",
+            type_name::<Dynamic<T>>(),
+        ))
+    }
+}
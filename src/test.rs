@@ -0,0 +1,4354 @@
+use crate::{static_service, Static};
+use anyhow::{anyhow, Result};
+use axum::http::{Request, Response};
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use std::any::type_name;
+use std::sync::LazyLock;
+use std_plus::{f, lazy_lock, new, to_static, Encoding as _, Standard, B64};
+use tower::BoxError;
+use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+type BoxBody = http_body_util::combinators::UnsyncBoxBody<Bytes, BoxError>;
+
+#[allow(dead_code)]
+pub struct Body(BoxBody);
+
+impl Body {
+    pub fn new<B>(body: B) -> Self
+    where
+        B: http_body::Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<BoxError>,
+    {
+        Self(body.map_err(Into::into).boxed_unsync())
+    }
+
+    pub fn empty() -> Self {
+        Self::new(http_body_util::Empty::new())
+    }
+}
+
+#[derive(Debug, new, Clone)]
+struct Data(&'static str);
+
+#[tokio::test]
+async fn static_service() -> Result<()> {
+    async fn handler(req: Request<Body>) -> Result<Response<String>> {
+        fn extractor<T>(req: &Request<Body>) -> Result<&'static T>
+        where
+            T: Send + Sync + 'static,
+        {
+            let error = anyhow!(f!("Failed to extract: {}", type_name::<T>()));
+            let Static(_ext) = req.extensions().get::<Static<T>>().ok_or(error)?;
+
+            Ok(*_ext)
+        }
+
+        let Data(data) = extractor::<Data>(&req)?;
+        let encoder = extractor::<B64<Standard>>(&req)?;
+        Ok(Response::new(encoder.encode(data).unwrap()))
+    }
+
+    static ENCODER: LazyLock<B64<Standard>> = lazy_lock!(B64::<Standard>::new());
+    let data: &'static Data = to_static!(Data, Data::new("West"));
+
+    let res = ServiceBuilder::new()
+        .layer(static_service!(data))
+        .layer(static_service!(&*ENCODER))
+        .service(service_fn(handler))
+        .oneshot(Request::new(Body::empty()))
+        .await?
+        .into_body();
+
+    assert_eq!("West", ENCODER.decode(res).unwrap());
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_params() -> Result<()> {
+    use crate::{BodyError, Query, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{extract::rejection::JsonRejection, routing::get, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Search {
+        #[validate(length(min = 1))]
+        q: Option<String>,
+    }
+
+    impl BodyError for Search {
+        type Error = Value;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            json!({ "reason": rejection.body_text() })
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            json!({ "reason": "invalid", "messages": err })
+        }
+    }
+
+    async fn handler(Query(search): Query<Search>) -> String {
+        search.q.unwrap_or_default()
+    }
+
+    let app = Router::new().route("/", get(handler));
+
+    // Missing and empty query strings both extract all-optional structs
+    for uri in ["/", "/?"] {
+        let res = app
+            .clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(OK, res.status(), "{uri}");
+    }
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/?q=west").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"west", &bytes[..]);
+
+    // An empty value is still validated
+    let res = app
+        .clone()
+        .oneshot(Request::get("/?q=").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("length", error["messages"]["q"][0]["code"]);
+
+    // Repeated keys fail like they do in serde_urlencoded
+    let res = app
+        .oneshot(Request::get("/?q=a&q=b").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    let expected = serde_urlencoded::from_str::<Search>("q=a&q=b").unwrap_err();
+    assert_eq!(
+        expected.to_string(),
+        error["messages"]["query"][0]["message"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn form_content_type() -> Result<()> {
+    use crate::{BodyError, Form, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY, UNSUPPORTED_MEDIA_TYPE};
+    use axum::{extract::rejection::JsonRejection, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Login {
+        #[validate(length(min = 3))]
+        name: String,
+    }
+
+    impl BodyError for Login {
+        type Error = Value;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            json!({ "reason": rejection.body_text() })
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            json!({ "reason": "invalid", "messages": err })
+        }
+    }
+
+    async fn handler(Form(login): Form<Login>) -> String {
+        login.name
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let request = |content_type: &str, body: &'static str| {
+        Request::post("/")
+            .header("content-type", content_type)
+            .body(axum::body::Body::from(body))
+    };
+
+    let res = app
+        .clone()
+        .oneshot(request("application/x-www-form-urlencoded", "name=west")?)
+        .await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"west", &bytes[..]);
+
+    // Parameters after the mime type are ignored
+    let res = app
+        .clone()
+        .oneshot(request(
+            "application/x-www-form-urlencoded; charset=utf-8",
+            "name=west",
+        )?)
+        .await?;
+    assert_eq!(OK, res.status());
+
+    // The content type is checked before the body is deserialized
+    let res = app
+        .clone()
+        .oneshot(request("application/json", "name=west")?)
+        .await?;
+    assert_eq!(UNSUPPORTED_MEDIA_TYPE, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(
+        "Expected request with `Content-Type: application/x-www-form-urlencoded`",
+        error["messages"]["content-type"][0]["message"]
+    );
+
+    let res = app
+        .clone()
+        .oneshot(request("application/x-www-form-urlencoded", "age=1")?)
+        .await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("parse", error["messages"]["form"][0]["code"]);
+
+    let res = app
+        .oneshot(request("application/x-www-form-urlencoded", "name=w")?)
+        .await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("length", error["messages"]["name"][0]["code"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn path_rejections() -> Result<()> {
+    use crate::{BodyError, Path, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{extract::rejection::JsonRejection, routing::get, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Item {
+        #[validate(range(min = 1, max = 100))]
+        id: u64,
+    }
+
+    impl BodyError for Item {
+        type Error = Value;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            json!({ "reason": rejection.body_text() })
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            json!({ "reason": "invalid", "messages": err })
+        }
+    }
+
+    async fn handler(Path(item): Path<Item>) -> String {
+        item.id.to_string()
+    }
+
+    let app = Router::new()
+        .route("/items/:id", get(handler))
+        .route("/items", get(handler));
+    let send = |uri: &'static str| {
+        app.clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    let res = send("/items/42").await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"42", &bytes[..]);
+
+    // Missing and malformed params go through `path_error`
+    for uri in ["/items", "/items/abc"] {
+        let res = send(uri).await?;
+        assert_eq!(BAD_REQUEST, res.status(), "{uri}");
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let error = serde_json::from_slice::<Value>(&bytes)?;
+        assert_eq!("parse", error["messages"]["path"][0]["code"], "{uri}");
+    }
+
+    // A failed constraint goes through `validate_error`
+    let res = send("/items/500").await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("range", error["messages"]["id"][0]["code"]);
+    assert!(error["messages"].get("path").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn derive_body_error() -> Result<()> {
+    use crate::{Body, BodyError, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate, BodyError)]
+    #[body_error(status = 422)]
+    struct CreateUser {
+        #[validate(length(min = 3, message = "name is too short"))]
+        name: String,
+    }
+
+    async fn handler(Body(user): Body<CreateUser>) -> String {
+        user.name
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"name":"west"}"#).await?;
+    assert_eq!(OK, res.status());
+
+    let res = send(r#"{"name":"w"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(
+        json!({
+            "message": "Invalid payload data!",
+            "fields": { "name": ["name is too short"] }
+        }),
+        error
+    );
+
+    // JSON failures use the same status and leave `fields` out
+    let res = send(r#"{"name":"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["message"].is_string());
+    assert!(error.get("fields").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn default_error() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, Query, CONFLICT, UNPROCESSABLE_ENTITY};
+    use axum::{
+        routing::{get, post},
+        Router,
+    };
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Item {
+        #[validate(range(min = 1, message = "id must be positive"))]
+        id: i64,
+    }
+
+    impl DefaultError for Item {}
+
+    #[derive(Deserialize, Validate)]
+    struct Conflicting {
+        #[validate(range(min = 1))]
+        id: i64,
+    }
+
+    impl DefaultError for Conflicting {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                validate_status: CONFLICT,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn body(Body(item): Body<Item>) -> String {
+        item.id.to_string()
+    }
+
+    async fn query(Query(item): Query<Conflicting>) -> String {
+        item.id.to_string()
+    }
+
+    let app = Router::new()
+        .route("/", post(body))
+        .route("/query", get(query));
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(r#"{"id":0}"#))?,
+        )
+        .await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(
+        json!({
+            "message": "Invalid payload data!",
+            "fields": { "id": ["id must be positive"] }
+        }),
+        error
+    );
+
+    // Overriding a `DefaultError` setting keeps the default payload
+    let res = app
+        .oneshot(Request::get("/query?id=0").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(CONFLICT, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("Invalid payload data!", error["message"]);
+    assert_eq!("range", error["fields"]["id"][0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn problem_json() -> Result<()> {
+    use crate::{
+        Body, BodyConfig, BodyError, Problem, ProblemJson, PROBLEM_JSON, UNPROCESSABLE_ENTITY,
+    };
+    use axum::{extract::rejection::JsonRejection, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Address {
+        #[validate(length(min = 5, message = "zip is too short"))]
+        zip: String,
+    }
+
+    impl BodyError for Address {
+        type Error = Problem;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            Problem::json(rejection)
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            Problem::validate(err)
+        }
+
+        fn config() -> BodyConfig {
+            BodyConfig {
+                json_status: UNPROCESSABLE_ENTITY,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn handler(ProblemJson(Body(address)): ProblemJson<Body<Address>>) -> String {
+        address.zip
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"zip":"1"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    assert_eq!(PROBLEM_JSON, res.headers()["content-type"]);
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let problem = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(422, problem["status"]);
+    assert_eq!("zip is too short", problem["errors"]["/zip"][0]);
+
+    // `Problem::json` defaults to 400, the rejection status wins
+    let res = send("{").await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let problem = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(422, problem["status"]);
+    assert_eq!("Unprocessable Entity", problem["title"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn json_and_validate_status() -> Result<()> {
+    use crate::{Body, BodyError, DefaultError, BAD_REQUEST, CONFLICT, UNPROCESSABLE_ENTITY};
+    use axum::{http::StatusCode, routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Defaults {
+        #[validate(range(min = 1))]
+        id: i64,
+    }
+
+    impl DefaultError for Defaults {}
+
+    #[derive(Deserialize, Validate, BodyError)]
+    #[body_error(json_status = 409, validate_status = 400)]
+    struct Derived {
+        #[validate(range(min = 1))]
+        id: i64,
+    }
+
+    async fn defaults(Body(body): Body<Defaults>) -> String {
+        body.id.to_string()
+    }
+
+    async fn derived(Body(body): Body<Derived>) -> String {
+        body.id.to_string()
+    }
+
+    let app = Router::new()
+        .route("/defaults", post(defaults))
+        .route("/derived", post(derived));
+    let send = |uri: &'static str, body: &'static str| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let cases: [(&str, &str, StatusCode); 4] = [
+        ("/defaults", "{", BAD_REQUEST),
+        ("/defaults", r#"{"id":0}"#, UNPROCESSABLE_ENTITY),
+        ("/derived", "{", CONFLICT),
+        ("/derived", r#"{"id":0}"#, BAD_REQUEST),
+    ];
+    for (uri, body, status) in cases {
+        let res = send(uri, body).await?;
+        assert_eq!(status, res.status(), "{uri} {body}");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "cbor")]
+#[tokio::test]
+async fn cbor_body() -> Result<()> {
+    use crate::{
+        Cbor, DefaultError, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY, UNSUPPORTED_MEDIA_TYPE,
+    };
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Reading {
+        #[validate(range(max = 100))]
+        value: u32,
+    }
+
+    impl DefaultError for Reading {}
+
+    async fn handler(Cbor(reading): Cbor<Reading>) -> String {
+        reading.value.to_string()
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |content_type: &'static str, body: Vec<u8>| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", content_type)
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+    let encode = |value: Value| {
+        let mut buf = Vec::new();
+        ciborium::into_writer(&value, &mut buf).unwrap();
+        buf
+    };
+
+    let res = send("application/cbor", encode(json!({ "value": 42 }))).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"42", &bytes[..]);
+
+    let res = send("application/cbor", encode(json!({ "value": 420 }))).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+
+    let res = send("application/cbor", encode(json!({ "value": "x" }))).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["fields"]["body"][0].is_string());
+
+    let res = send("application/json", br#"{"value":42}"#.to_vec()).await?;
+    assert_eq!(UNSUPPORTED_MEDIA_TYPE, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn missing_static() -> Result<()> {
+    use crate::INTERNAL_SERVER_ERROR;
+    use axum::{routing::get, Router};
+
+    async fn handler(Static(data): Static<Data>) -> &'static str {
+        data.0
+    }
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    let rejected = res.extensions().get::<crate::RejectedBy>();
+    assert_eq!(Some(type_name::<Static<Data>>()), rejected.map(|by| by.0));
+
+    // The previous tuple rejection is still reachable through `From`
+    let rejection = crate::ExtensionRejection::new(INTERNAL_SERVER_ERROR, "Missing", "Static");
+    let tuple: (axum::http::StatusCode, &str) = rejection.into();
+    assert_eq!((INTERNAL_SERVER_ERROR, "Missing"), tuple);
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_without_clone() -> Result<()> {
+    use crate::RouterStaticExt;
+    use axum::{routing::get, Router};
+    use std::sync::Mutex;
+
+    // Neither `Clone` nor `Copy`, only `Sync`
+    struct Counter {
+        hits: Mutex<u32>,
+    }
+
+    async fn handler(Static(counter): Static<Counter>) -> String {
+        let mut hits = counter.hits.lock().unwrap();
+        *hits += 1;
+        hits.to_string()
+    }
+
+    static COUNTER: Counter = Counter {
+        hits: Mutex::new(0),
+    };
+
+    let app = Router::new().route("/", get(handler)).with_static(&COUNTER);
+
+    for expected in ["1", "2"] {
+        let res = app
+            .clone()
+            .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+            .await?;
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(expected.as_bytes(), &bytes[..]);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_in_map_pipeline() -> Result<()> {
+    use crate::StaticLayer;
+    use axum::http::HeaderValue;
+
+    static DATA: Data = Data("West");
+
+    async fn handler(req: Request<Body>) -> Result<Response<String>> {
+        let Static(data) = req
+            .extensions()
+            .get::<Static<Data>>()
+            .copied()
+            .ok_or(anyhow!("Failed to extract: Data"))?;
+        let suffix = req
+            .headers()
+            .get("x-suffix")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        Ok(Response::new(f!("{}{}", data.0, suffix)))
+    }
+
+    let service = ServiceBuilder::new()
+        .layer(StaticLayer::new(&DATA))
+        .map_request(|mut req: Request<Body>| {
+            req.headers_mut()
+                .insert("x-suffix", HeaderValue::from_static("!"));
+            req
+        })
+        .map_response(|res: Response<String>| res.map(|body| body.to_uppercase()))
+        .service_fn(handler);
+
+    // Every clone of the stack inserts the static on its own calls
+    for _ in 0..2 {
+        let res = service.clone().oneshot(Request::new(Body::empty())).await?;
+        assert_eq!("WEST!", res.into_body());
+    }
+
+    // Requests rebuilt from their parts keep the extensions
+    let service = ServiceBuilder::new()
+        .map_request(|req: Request<Body>| req)
+        .layer(StaticLayer::new(&DATA))
+        .map_request(|req: Request<Body>| {
+            let (parts, body) = req.into_parts();
+            Request::from_parts(parts, body)
+        })
+        .service_fn(handler);
+
+    let res = service.oneshot(Request::new(Body::empty())).await?;
+    assert_eq!("West", res.into_body());
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_trait_object() -> Result<()> {
+    use crate::{OptionalStatic, StaticLayer};
+    use axum::{routing::get, Router};
+
+    trait Repository: Sync {
+        fn name(&self) -> &'static str;
+    }
+
+    struct Memory;
+
+    impl Repository for Memory {
+        fn name(&self) -> &'static str {
+            "memory"
+        }
+    }
+
+    static MEMORY: Memory = Memory;
+
+    async fn handler(
+        repository: Static<dyn Repository>,
+        OptionalStatic(optional): OptionalStatic<dyn Repository>,
+    ) -> String {
+        f!("{} {}", repository.name(), optional.is_some())
+    }
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .layer(StaticLayer::<dyn Repository>::new(&MEMORY))
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"memory true", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_never_clones() -> Result<()> {
+    use crate::RouterStaticExt;
+    use axum::{routing::get, Router};
+    use std::collections::HashSet;
+
+    struct Allowlist(HashSet<&'static str>);
+
+    impl Clone for Allowlist {
+        fn clone(&self) -> Self {
+            panic!("Allowlist was cloned");
+        }
+    }
+
+    async fn handler(allowlist: Static<Allowlist>) -> String {
+        allowlist.0.contains("west.dev").to_string()
+    }
+
+    static ALLOWLIST: LazyLock<Allowlist> =
+        LazyLock::new(|| Allowlist(HashSet::from(["west.dev"])));
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .with_static(&*ALLOWLIST)
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"true", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn innermost_static_wins() -> Result<()> {
+    use crate::StaticLayer;
+    use axum::{routing::get, Router};
+
+    async fn handler(Static(name): Static<&'static str>) -> &'static str {
+        name
+    }
+
+    static DEFAULT: &str = "router";
+    static OVERRIDE: &str = "route";
+
+    let app = Router::new()
+        .route("/default", get(handler))
+        .route("/override", get(handler).layer(StaticLayer::new(&OVERRIDE)))
+        .layer(StaticLayer::new(&DEFAULT));
+
+    for (uri, expected) in [("/default", "router"), ("/override", "route")] {
+        let res = app
+            .clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty())?)
+            .await?;
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(expected.as_bytes(), &bytes[..]);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unknown_field() -> Result<()> {
+    use crate::{classify, DefaultError, JsonErrorKind, BAD_REQUEST};
+    use axum::extract::rejection::JsonRejection;
+    use axum::{routing::post, Json, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    #[serde(deny_unknown_fields)]
+    struct Strict {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    impl DefaultError for Strict {}
+
+    async fn handler(_: crate::Body<Strict>) {}
+
+    let rejection: JsonRejection = Json::<Strict>::from_bytes(br#"{"name":"a","nmae":"b"}"#)
+        .err()
+        .unwrap();
+    assert_eq!(JsonErrorKind::UnknownField, classify(&rejection));
+    assert_eq!(Some("nmae".into()), crate::unknown_field(&rejection));
+
+    let res = Router::new()
+        .route("/", post(handler))
+        .oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(r#"{"name":"a","nmae":"b"}"#))?,
+        )
+        .await?;
+
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert_eq!("Unknown field", error["fields"]["nmae"][0]);
+
+    // The derived error reports it the same way
+    #[derive(Deserialize, Validate, crate::BodyError)]
+    #[serde(deny_unknown_fields)]
+    struct Derived {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    let rejection = Json::<Derived>::from_bytes(br#"{"name":"a","nmae":"b"}"#)
+        .err()
+        .unwrap();
+    let error = serde_json::to_value(<Derived as crate::BodyError>::json_error(rejection))?;
+    assert_eq!("Unknown field", error["fields"]["nmae"][0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn index_map_order() -> Result<()> {
+    use crate::{DefaultError, OK};
+    use axum::{routing::post, Json, Router};
+    use indexmap::IndexMap;
+    use serde::{Deserialize, Serialize};
+    use validator::Validate;
+
+    #[derive(Serialize, Deserialize, Validate)]
+    struct Labels {
+        #[validate(length(min = 1))]
+        name: String,
+        labels: IndexMap<String, String>,
+    }
+
+    impl DefaultError for Labels {}
+
+    async fn handler(crate::Body(labels): crate::Body<Labels>) -> Json<Labels> {
+        Json(labels)
+    }
+
+    let body = r#"{"name":"west","labels":{"zone":"b","app":"api","env":"prod"}}"#;
+    let res = Router::new()
+        .route("/", post(handler))
+        .oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))?,
+        )
+        .await?;
+
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(body.as_bytes(), &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn charset_content_types() -> Result<()> {
+    use crate::{DefaultError, OK, UNSUPPORTED_MEDIA_TYPE};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Note {
+        #[allow(dead_code)]
+        text: String,
+    }
+
+    impl DefaultError for Note {}
+
+    async fn handler(_: crate::Body<Note>) {}
+
+    let app = Router::new().route("/", post(handler));
+    let send = |content_type: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", content_type)
+                .body(axum::body::Body::from(r#"{"text":"hi"}"#))
+                .unwrap(),
+        )
+    };
+
+    for content_type in [
+        "application/json",
+        "application/json; charset=utf-8",
+        "Application/JSON;charset=UTF-8",
+        "application/vnd.api+json",
+    ] {
+        assert_eq!(OK, send(content_type).await?.status(), "{content_type}");
+    }
+
+    let res = send("text/plain").await?;
+    assert_eq!(UNSUPPORTED_MEDIA_TYPE, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert!(error["message"]
+        .as_str()
+        .is_some_and(|message| message.contains("Content-Type")));
+    Ok(())
+}
+
+#[cfg(feature = "multipart")]
+#[tokio::test]
+async fn multipart_file_rules() -> Result<()> {
+    use crate::{
+        DefaultError, FileRule, MultipartFiles, MultipartForm, UploadedFile, OK, PAYLOAD_TOO_LARGE,
+        UNSUPPORTED_MEDIA_TYPE,
+    };
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Profile {
+        #[allow(dead_code)]
+        name: String,
+        #[serde(skip)]
+        avatar: Option<UploadedFile>,
+    }
+
+    impl DefaultError for Profile {}
+
+    impl MultipartFiles for Profile {
+        fn insert_file(&mut self, field: &str, file: UploadedFile) -> Result<(), UploadedFile> {
+            if field != "avatar" {
+                return Err(file);
+            }
+
+            self.avatar = Some(file);
+            Ok(())
+        }
+
+        fn file_rule(field: &str) -> Option<FileRule> {
+            match field {
+                "avatar" => Some(FileRule {
+                    content_types: &["image/*"],
+                    max_bytes: Some(16),
+                }),
+                _ => None,
+            }
+        }
+    }
+
+    async fn handler(MultipartForm(profile): MultipartForm<Profile>) -> String {
+        profile
+            .avatar
+            .map(|file| file.bytes.len())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |content_type: &str, avatar: &str| {
+        let body = f!(
+            "--X\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nwest\r\n\
+             --X\r\nContent-Disposition: form-data; name=\"avatar\"; filename=\"a\"\r\n\
+             Content-Type: {}\r\n\r\n{}\r\n--X--\r\n",
+            content_type,
+            avatar
+        );
+
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "multipart/form-data; boundary=X")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    assert_eq!(OK, send("image/png", "tiny").await?.status());
+    assert_eq!(
+        UNSUPPORTED_MEDIA_TYPE,
+        send("application/pdf", "tiny").await?.status()
+    );
+    assert_eq!(
+        PAYLOAD_TOO_LARGE,
+        send("image/png", &"x".repeat(64)).await?.status()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn empty_body_error() -> Result<()> {
+    use crate::{DefaultError, BAD_REQUEST};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Required {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    impl DefaultError for Required {}
+
+    async fn handler(_: crate::Body<Required>) {}
+
+    let res = Router::new()
+        .route("/", post(handler))
+        .oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .header("content-length", "0")
+                .body(axum::body::Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert_eq!(
+        "Request body is empty, expected a JSON payload",
+        error["fields"]["body"][0]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_depth() -> Result<()> {
+    use crate::{flatten_errors, BodyConfig, BodyError, DefaultError, BAD_REQUEST};
+    use axum::extract::rejection::JsonRejection;
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Tree {
+        #[allow(dead_code)]
+        children: Vec<serde_json::Value>,
+    }
+
+    impl DefaultError for Tree {}
+
+    #[derive(Deserialize, Validate)]
+    struct Shallow {
+        #[allow(dead_code)]
+        children: Vec<serde_json::Value>,
+    }
+
+    impl BodyError for Shallow {
+        type Error = serde_json::Value;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            serde_json::json!({ "message": rejection.body_text() })
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            serde_json::json!({ "fields": flatten_errors(&err) })
+        }
+
+        fn config() -> BodyConfig {
+            BodyConfig {
+                max_depth: Some(16),
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn tree(_: crate::Body<Tree>) {}
+
+    async fn shallow(_: crate::Body<Shallow>) {}
+
+    let app = Router::new()
+        .route("/tree", post(tree))
+        .route("/shallow", post(shallow));
+    let send = |uri: &'static str, body: String| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+    let nested = |depth| {
+        f!(
+            r#"{{"children": {}"[]"{}}}"#,
+            "[".repeat(depth),
+            "]".repeat(depth)
+        )
+    };
+
+    // Without a limit serde_json's own recursion limit rejects it cleanly
+    let res = send("/tree", nested(100_000)).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert!(error["message"]
+        .as_str()
+        .is_some_and(|message| message.contains("recursion limit")));
+
+    let res = send("/shallow", nested(100_000)).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert_eq!(
+        "JSON is nested deeper than 16 levels",
+        error["fields"]["body"][0]
+    );
+
+    let res = send("/shallow", nested(8)).await?;
+    assert_eq!(crate::OK, res.status());
+    Ok(())
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn msgpack_body() -> Result<()> {
+    use crate::{DefaultError, MsgPack, OK};
+    use axum::{routing::post, Router};
+    use serde::{Deserialize, Serialize};
+    use validator::Validate;
+
+    #[derive(Debug, Serialize, Deserialize, Validate)]
+    struct Telemetry {
+        #[validate(range(min = 0.0, max = 100.0))]
+        battery: f32,
+        device: String,
+    }
+
+    impl DefaultError for Telemetry {}
+
+    async fn handler(MsgPack(telemetry): MsgPack<Telemetry>) -> String {
+        telemetry.device
+    }
+
+    let body = rmp_serde::to_vec_named(&Telemetry {
+        battery: 42.0,
+        device: "sensor".into(),
+    })?;
+
+    let res = Router::new()
+        .route("/", post(handler))
+        .oneshot(
+            Request::post("/")
+                .header("content-type", "application/msgpack")
+                .body(axum::body::Body::from(body))?,
+        )
+        .await?;
+
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"sensor", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn dynamic_service() -> Result<()> {
+    use crate::{dynamic_service, Dynamic, INTERNAL_SERVER_ERROR, OK};
+    use axum::{routing::get, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Hits(AtomicUsize);
+
+    async fn handler(Dynamic(hits): Dynamic<Hits>) -> String {
+        (hits.0.fetch_add(1, Ordering::SeqCst) + 1).to_string()
+    }
+
+    let hits = std::sync::Arc::new(Hits(AtomicUsize::new(0)));
+    let app = Router::new()
+        .route("/", get(handler))
+        .layer(dynamic_service!(hits.clone()));
+
+    for expected in ["1", "2"] {
+        let res = app
+            .clone()
+            .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(OK, res.status());
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(expected.as_bytes(), &bytes[..]);
+    }
+
+    // The layer shares the value instead of copying it
+    assert_eq!(2, hits.0.load(Ordering::SeqCst));
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_service_many() -> Result<()> {
+    use crate::OK;
+    use axum::{routing::get, Router};
+
+    #[derive(Clone)]
+    struct Name(&'static str);
+
+    #[derive(Clone)]
+    struct Greeting(&'static str);
+
+    async fn handler(Static(name): Static<Name>, Static(greeting): Static<Greeting>) -> String {
+        f!("{}, {}", greeting.0, name.0)
+    }
+
+    static NAME: Name = Name("West");
+    static GREETING: Greeting = Greeting("Hello");
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .layer(static_service!(&NAME, &GREETING,))
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"Hello, West", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_tuples() -> Result<()> {
+    use crate::{Static2, Static3, OK};
+    use axum::{routing::get, Router};
+
+    #[derive(Clone)]
+    struct Host(&'static str);
+
+    #[derive(Clone)]
+    struct Port(u16);
+
+    #[derive(Clone)]
+    struct Scheme(&'static str);
+
+    async fn pair(Static2(host, port): Static2<Host, Port>) -> String {
+        f!("{}:{}", host.0, port.0)
+    }
+
+    async fn triple(Static3(scheme, host, port): Static3<Scheme, Host, Port>) -> String {
+        f!("{}://{}:{}", scheme.0, host.0, port.0)
+    }
+
+    static HOST: Host = Host("localhost");
+    static PORT: Port = Port(8080);
+    static SCHEME: Scheme = Scheme("https");
+
+    let app = Router::new()
+        .route("/pair", get(pair))
+        .route("/triple", get(triple))
+        .layer(static_service!(&HOST, &PORT, &SCHEME));
+
+    for (uri, expected) in [
+        ("/pair", "localhost:8080"),
+        ("/triple", "https://localhost:8080"),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(OK, res.status());
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(expected.as_bytes(), &bytes[..]);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn header_names() -> Result<()> {
+    use crate::{DefaultError, Header, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::get, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Client {
+        #[validate(range(min = 1, max = 3))]
+        x_api_version: u8,
+        accept_language: Option<String>,
+    }
+
+    impl DefaultError for Client {}
+
+    async fn handler(Header(client): Header<Client>) -> String {
+        f!(
+            "{} {}",
+            client.x_api_version,
+            client.accept_language.unwrap_or_default()
+        )
+    }
+
+    let app = Router::new().route("/", get(handler));
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::get("/")
+                .header("X-Api-Version", "2")
+                .header("accept-language", "en")
+                .header("accept-language", "fr;q=0.5")
+                .body(axum::body::Body::empty())?,
+        )
+        .await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"2 en, fr;q=0.5", &bytes[..]);
+
+    let res = app
+        .clone()
+        .oneshot(
+            Request::get("/")
+                .header("x-api-version", "9")
+                .body(axum::body::Body::empty())?,
+        )
+        .await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+
+    // A repeated scalar header is joined too, so it fails to parse instead of
+    // silently keeping one of the values
+    for headers in [&[][..], &["1", "2"][..]] {
+        let mut req = Request::get("/");
+        for value in headers {
+            req = req.header("x-api-version", *value);
+        }
+
+        let res = app
+            .clone()
+            .oneshot(req.body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(BAD_REQUEST, res.status());
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let error = serde_json::from_slice::<Value>(&bytes)?;
+        assert!(error["fields"]["headers"][0].is_string());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_bytes() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, OK, PAYLOAD_TOO_LARGE};
+    use axum::{extract::DefaultBodyLimit, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Note {
+        text: String,
+    }
+
+    impl DefaultError for Note {}
+
+    #[derive(Deserialize, Validate)]
+    struct Tweet {
+        text: String,
+    }
+
+    impl DefaultError for Tweet {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                max_bytes: Some(16),
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn note(Body(note): Body<Note>) -> String {
+        note.text
+    }
+
+    async fn tweet(Body(tweet): Body<Tweet>) -> String {
+        tweet.text
+    }
+
+    let app = Router::new()
+        .route("/note", post(note))
+        .route("/tweet", post(tweet))
+        .layer(DefaultBodyLimit::max(32));
+    let send = |uri: &'static str, body: String| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+    let text = |len: usize| f!(r#"{{"text":"{}"}}"#, "a".repeat(len));
+
+    let res = send("/tweet", text(4)).await?;
+    assert_eq!(OK, res.status());
+
+    let res = send("/tweet", text(20)).await?;
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(
+        "Body exceeds the limit of 16 bytes",
+        error["fields"]["body"][0]
+    );
+
+    let res = send("/note", text(20)).await?;
+    assert_eq!(OK, res.status());
+
+    // Without `max_bytes` the router limit still answers with its own 413
+    let res = send("/note", text(40)).await?;
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["fields"]["body"][0].is_string());
+    Ok(())
+}
+
+#[tokio::test]
+async fn body_context() -> Result<()> {
+    use crate::{
+        BodyContext, DefaultError, ValidateContext, INTERNAL_SERVER_ERROR, OK, UNPROCESSABLE_ENTITY,
+    };
+    use axum::{routing::post, Extension, Router};
+    use serde::Deserialize;
+    use validator::{Validate, ValidationError};
+
+    #[derive(Clone)]
+    struct Reserved(&'static [&'static str]);
+
+    fn available(name: &String, reserved: &Reserved) -> Result<(), ValidationError> {
+        match reserved.0.contains(&name.as_str()) {
+            true => Err(ValidationError::new("reserved")),
+            false => Ok(()),
+        }
+    }
+
+    #[derive(Deserialize, Validate)]
+    #[validate(context = Reserved)]
+    struct Signup {
+        #[validate(custom(function = "available", use_context))]
+        name: String,
+    }
+
+    impl DefaultError for Signup {}
+
+    impl ValidateContext for Signup {
+        type Context = Reserved;
+    }
+
+    async fn handler(BodyContext(signup): BodyContext<Signup>) -> String {
+        signup.name
+    }
+
+    let router = Router::new().route("/", post(handler));
+    let app = router
+        .clone()
+        .layer(Extension(Reserved(&["admin", "root"])));
+    let request = |name: &str| {
+        Request::post("/")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(f!(r#"{{"name":"{}"}}"#, name)))
+    };
+
+    let res = app.clone().oneshot(request("west")?).await?;
+    assert_eq!(OK, res.status());
+
+    let res = app.oneshot(request("admin")?).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert_eq!("reserved", error["fields"]["name"][0]);
+
+    // A missing context is a server error, not a client one
+    let res = router.oneshot(request("west")?).await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn body_query() -> Result<()> {
+    use crate::{BodyQuery, DefaultError, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Comment {
+        #[validate(length(min = 1))]
+        text: String,
+    }
+
+    impl DefaultError for Comment {}
+
+    #[derive(Deserialize, Validate)]
+    struct Thread {
+        #[validate(range(min = 1))]
+        thread: u64,
+    }
+
+    impl DefaultError for Thread {}
+
+    async fn handler(BodyQuery(comment, thread): BodyQuery<Comment, Thread>) -> String {
+        f!("{} {}", thread.thread, comment.text)
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |uri: &'static str, body: &'static str| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send("/?thread=7", r#"{"text":"hi"}"#).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"7 hi", &bytes[..]);
+
+    let res = send("/?thread=0", r#"{"text":"hi"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error.get("body").is_none());
+    assert!(error["query"]["fields"]["thread"][0].is_string());
+
+    // Both failures are reported, the body's status wins
+    let res = send("/?thread=0", "{").await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["body"]["message"].is_string());
+    assert!(error["query"]["fields"]["thread"][0].is_string());
+    Ok(())
+}
+
+#[tokio::test]
+async fn created_response() -> Result<()> {
+    use crate::{Created, CREATED};
+    use axum::{routing::post, Router};
+    use serde_json::{json, Value};
+
+    async fn with_location() -> Created<Value> {
+        Created(json!({ "id": 7 }), Some("/users/7".into()))
+    }
+
+    async fn without_location() -> Created<Value> {
+        Created(json!({ "id": 7 }), None)
+    }
+
+    async fn invalid_location() -> Created<Value> {
+        Created(json!({ "id": 7 }), Some("/users/\n7".into()))
+    }
+
+    let app = Router::new()
+        .route("/with", post(with_location))
+        .route("/without", post(without_location))
+        .route("/invalid", post(invalid_location));
+
+    for (uri, location) in [
+        ("/with", Some("/users/7")),
+        ("/without", None),
+        ("/invalid", None),
+    ] {
+        let res = app
+            .clone()
+            .oneshot(Request::post(uri).body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(CREATED, res.status(), "{uri}");
+        assert_eq!(
+            location,
+            res.headers()
+                .get("location")
+                .map(|value| value.to_str().unwrap()),
+            "{uri}"
+        );
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let body = serde_json::from_slice::<Value>(&bytes)?;
+        assert_eq!(json!({ "id": 7 }), body, "{uri}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_config() -> Result<()> {
+    use crate::{StaticConfig, SERVICE_UNAVAILABLE};
+    use axum::{routing::get, Extension, Router};
+
+    async fn handler(Static(data): Static<Data>) -> &'static str {
+        data.0
+    }
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .layer(Extension(StaticConfig::<Data>::new(
+            SERVICE_UNAVAILABLE,
+            "Not ready yet",
+        )))
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+
+    assert_eq!(SERVICE_UNAVAILABLE, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"Not ready yet", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn json_error_with_body() -> Result<()> {
+    use crate::{Body, BodyError, BAD_REQUEST};
+    use axum::{extract::rejection::JsonRejection, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Event {
+        #[allow(dead_code)]
+        kind: String,
+    }
+
+    impl BodyError for Event {
+        type Error = Value;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            json!({ "reason": rejection.body_text() })
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            json!({ "reason": "invalid", "messages": err })
+        }
+
+        fn json_error_with_body(rejection: JsonRejection, body: Bytes) -> Self::Error {
+            json!({
+                "reason": rejection.body_text(),
+                "body": String::from_utf8_lossy(&body)
+            })
+        }
+    }
+
+    async fn handler(Body(_): Body<Event>) {}
+
+    let app = Router::new().route("/", post(handler));
+    let send = |content_type: &'static str, body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", content_type)
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // Syntax and data errors get the body that failed to parse
+    for body in [r#"{"kind":"#, r#"{"kind":1}"#] {
+        let res = send("application/json", body).await?;
+        assert_eq!(BAD_REQUEST, res.status());
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let error = serde_json::from_slice::<Value>(&bytes)?;
+        assert_eq!(body, error["body"], "{body}");
+    }
+
+    // The body is never read for a wrong content type
+    let res = send("text/plain", r#"{"kind":"a"}"#).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error.get("body").is_none());
+    assert!(error["reason"].is_string());
+    Ok(())
+}
+
+#[test]
+fn flatten_list_errors() {
+    use crate::flatten_errors;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Item {
+        #[validate(length(min = 1, message = "sku is required"))]
+        sku: String,
+    }
+
+    #[derive(Validate)]
+    struct Order {
+        #[validate(length(min = 3, message = "name is too short"))]
+        name: String,
+
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    let order = Order {
+        name: "ab".into(),
+        items: vec![
+            Item { sku: "A1".into() },
+            Item { sku: "".into() },
+            Item { sku: "".into() },
+        ],
+    };
+
+    let errors = flatten_errors(&order.validate().unwrap_err());
+
+    assert_eq!(3, errors.len());
+    assert_eq!(vec!["name is too short"], errors["name"]);
+    assert_eq!(vec!["sku is required"], errors["items.1.sku"]);
+    assert_eq!(vec!["sku is required"], errors["items.2.sku"]);
+    assert!(!errors.contains_key("items.0.sku"));
+}
+
+#[test]
+fn schema_errors() {
+    use crate::{BodyConfig, BodyError, DefaultError};
+    use validator::{Validate, ValidationError};
+
+    #[derive(Validate)]
+    #[validate(schema(function = "passwords_match"))]
+    struct SignUp {
+        password: String,
+        confirmation: String,
+    }
+
+    fn passwords_match(sign_up: &SignUp) -> Result<(), ValidationError> {
+        match sign_up.password == sign_up.confirmation {
+            true => Ok(()),
+            false => Err(ValidationError::new("mismatch").with_message("passwords differ".into())),
+        }
+    }
+
+    impl DefaultError for SignUp {}
+
+    let sign_up = SignUp {
+        password: "hunter2".into(),
+        confirmation: "hunter3".into(),
+    };
+
+    let error = SignUp::validate_error(sign_up.validate().unwrap_err());
+    let error = serde_json::to_value(error).unwrap();
+    assert_eq!("passwords differ", error["fields"]["__all__"][0]);
+
+    // Overridden through `DefaultError` and through the derive
+    #[derive(Validate)]
+    #[validate(schema(function = "always_fails"))]
+    struct Renamed {
+        #[allow(dead_code)]
+        password: String,
+    }
+
+    impl DefaultError for Renamed {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                schema_key: "_schema",
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    #[derive(Validate, crate::BodyError)]
+    #[validate(schema(function = "always_fails"))]
+    #[body_error(schema_key = "_schema")]
+    struct Derived {
+        #[allow(dead_code)]
+        password: String,
+    }
+
+    fn always_fails<T>(_: &T) -> Result<(), ValidationError> {
+        Err(ValidationError::new("mismatch").with_message("passwords differ".into()))
+    }
+
+    let renamed = Renamed {
+        password: "hunter2".into(),
+    };
+    let error = Renamed::validate_error(renamed.validate().unwrap_err());
+    let error = serde_json::to_value(error).unwrap();
+    assert_eq!("passwords differ", error["fields"]["_schema"][0]);
+
+    let derived = Derived {
+        password: "hunter2".into(),
+    };
+    let error = Derived::validate_error(derived.validate().unwrap_err());
+    let error = serde_json::to_value(error).unwrap();
+    assert_eq!("passwords differ", error["fields"]["_schema"][0]);
+}
+
+#[tokio::test]
+async fn localized_body() -> Result<()> {
+    use crate::{DefaultError, LocalizedBody, MessageResolver, Resolver, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Extension, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use std::{borrow::Cow, collections::HashMap, sync::Arc};
+    use validator::Validate;
+
+    struct French;
+
+    impl MessageResolver for French {
+        fn message(
+            &self,
+            code: &str,
+            params: &HashMap<Cow<'static, str>, Value>,
+        ) -> Option<String> {
+            match code {
+                "length" => Some(f!("au moins {} caractères", params["min"])),
+                _ => None,
+            }
+        }
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Profile {
+        #[validate(length(min = 3))]
+        name: String,
+
+        #[validate(email(message = "invalid email"))]
+        email: String,
+    }
+
+    impl DefaultError for Profile {}
+
+    async fn handler(LocalizedBody(profile): LocalizedBody<Profile>) -> String {
+        profile.name
+    }
+
+    let router = Router::new().route("/", post(handler));
+    let request = || {
+        Request::post("/")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(r#"{"name":"w","email":"west"}"#))
+    };
+
+    let res = router
+        .clone()
+        .layer(Extension(Resolver(Arc::new(French))))
+        .oneshot(request()?)
+        .await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("au moins 3 caractères", error["fields"]["name"][0]);
+    // Codes the resolver doesn't know keep their message
+    assert_eq!("invalid email", error["fields"]["email"][0]);
+
+    let res = router.oneshot(request()?).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("length", error["fields"]["name"][0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn negotiate_accept() -> Result<()> {
+    use crate::{DefaultError, Negotiate, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Signup {
+        #[allow(dead_code)]
+        #[validate(length(min = 3, message = "name is too short"))]
+        name: String,
+    }
+
+    impl DefaultError for Signup {}
+
+    async fn handler(_: Negotiate<crate::Body<Signup>>) {}
+
+    let app = Router::new().route("/", post(handler));
+    let send = |accept: Option<&'static str>| {
+        let mut req = Request::post("/").header("content-type", "application/json");
+        if let Some(accept) = accept {
+            req = req.header("accept", accept);
+        }
+
+        app.clone()
+            .oneshot(req.body(axum::body::Body::from(r#"{"name":"w"}"#)).unwrap())
+    };
+
+    for accept in [
+        None,
+        Some("application/json"),
+        Some("*/*"),
+        Some("image/png"),
+        Some("image/png, application/*;q=0.5"),
+        Some("text/*;q=0.9, application/json"),
+        Some("text/plain;q=0, application/json"),
+        Some("text/plain;q=0"),
+    ] {
+        let res = send(accept).await?;
+        assert_eq!(UNPROCESSABLE_ENTITY, res.status(), "{accept:?}");
+        assert_eq!(
+            "application/json",
+            res.headers()["content-type"],
+            "{accept:?}"
+        );
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+        assert_eq!(
+            "name is too short", error["fields"]["name"][0],
+            "{accept:?}"
+        );
+    }
+
+    for accept in [
+        "text/plain",
+        "text/*;q=0.9, application/json;q=0.5",
+        "application/json;q=0.1, text/plain;Q=0.2",
+    ] {
+        let res = send(Some(accept)).await?;
+        assert_eq!(UNPROCESSABLE_ENTITY, res.status(), "{accept}");
+        assert!(res.headers()["content-type"]
+            .to_str()?
+            .starts_with("text/plain"));
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let lines = std::str::from_utf8(&bytes)?.lines().collect::<Vec<_>>();
+        assert!(
+            lines.contains(&"fields.name: name is too short"),
+            "{accept}"
+        );
+        assert!(
+            lines.contains(&"message: Invalid payload data!"),
+            "{accept}"
+        );
+    }
+
+    #[cfg(feature = "xml")]
+    {
+        let res = send(Some("application/xml")).await?;
+        assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+        assert_eq!("application/xml", res.headers()["content-type"]);
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let xml = std::str::from_utf8(&bytes)?;
+        assert!(xml.starts_with("<error>"));
+        assert!(xml.contains("name is too short"));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn ndjson_chunks() -> Result<()> {
+    use crate::{DefaultError, NdJson, NdJsonStream, BAD_REQUEST, OK, PAYLOAD_TOO_LARGE};
+    use axum::{extract::DefaultBodyLimit, routing::post, Router};
+    use futures_util::StreamExt;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Point {
+        x: i32,
+    }
+
+    impl DefaultError for Point {}
+
+    async fn buffered(NdJson(points): NdJson<Point>) -> String {
+        points.iter().map(|point| point.x).sum::<i32>().to_string()
+    }
+
+    async fn streamed(NdJsonStream(mut points): NdJsonStream<Point>) -> String {
+        let mut out = Vec::new();
+        while let Some(point) = points.next().await {
+            out.push(match point {
+                Ok(point) => point.x.to_string(),
+                Err(err) => f!("line {}", err.line),
+            });
+        }
+        out.join(",")
+    }
+
+    let app = Router::new()
+        .route("/buffered", post(buffered))
+        .route("/streamed", post(streamed))
+        .layer(DefaultBodyLimit::max(64));
+    let send = |uri: &'static str, chunks: Vec<&'static str>| {
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| Ok::<_, std::io::Error>(Bytes::from_static(chunk.as_bytes())));
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/x-ndjson")
+                .body(axum::body::Body::from_stream(futures_util::stream::iter(
+                    chunks,
+                )))
+                .unwrap(),
+        )
+    };
+
+    // The first record is split across three chunks
+    let split = vec![r#"{"x""#, ":1", "}\n{\"x\":2}\n", "\n{\"x\":3}"];
+    let res = send("/buffered", split.clone()).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"6", &bytes[..]);
+
+    let res = send("/streamed", split).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"1,2,3", &bytes[..]);
+
+    // A bad middle line doesn't stop the lines after it, blank lines still count
+    let bad = vec!["{\"x\":1}\n{\"x\":", "oops}\n\n{\"x\":3}\n"];
+    let res = send("/streamed", bad.clone()).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"1,line 2,3", &bytes[..]);
+
+    let res = send("/buffered", bad).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let errors = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(1, errors.as_array().unwrap().len());
+    assert_eq!(json!(2), errors[0]["line"]);
+    assert!(errors[0]["error"]["message"].is_string());
+
+    // The route's body limit bounds buffered bodies
+    let res = send("/buffered", vec!["{\"x\":1}\n"; 10]).await?;
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn valid_wrapper() -> Result<()> {
+    use crate::{DefaultError, Valid, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{
+        extract::Query,
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Page {
+        #[validate(range(min = 1, max = 50))]
+        size: u32,
+    }
+
+    impl DefaultError for Page {}
+
+    async fn json(Valid(Json(page)): Valid<Json<Page>>) -> String {
+        page.size.to_string()
+    }
+
+    async fn query(Valid(Query(page)): Valid<Query<Page>>) -> String {
+        page.size.to_string()
+    }
+
+    let app = Router::new()
+        .route("/json", post(json))
+        .route("/query", get(query));
+    let json_request = |body: &'static str| {
+        Request::post("/json")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body))
+    };
+
+    let res = app.clone().oneshot(json_request(r#"{"size":10}"#)?).await?;
+    assert_eq!(OK, res.status());
+
+    let res = app.clone().oneshot(json_request(r#"{"size":99}"#)?).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("range", error["fields"]["size"][0]);
+
+    // Rejections of the inner extractor are axum's own
+    let res = app.clone().oneshot(json_request(r#"{"size":"#)?).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    assert_eq!("text/plain; charset=utf-8", res.headers()["content-type"]);
+
+    let res = app
+        .clone()
+        .oneshot(Request::get("/query?size=0").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+
+    let res = app
+        .oneshot(Request::get("/query?size=5").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"5", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn validate_response_layer() -> Result<()> {
+    use crate::{ValidateResponseLayer, INTERNAL_SERVER_ERROR, NOT_FOUND, OK};
+    use axum::{extract::Path, routing::get, Json, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct User {
+        #[validate(length(min = 1))]
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    async fn handler(Path(name): Path<String>) -> Result<Json<Value>, NotFound> {
+        match name.as_str() {
+            "missing" => Err(NotFound),
+            "empty" => Ok(Json(json!({ "name": "" }))),
+            name => Ok(Json(json!({ "name": name }))),
+        }
+    }
+
+    struct NotFound;
+
+    impl axum::response::IntoResponse for NotFound {
+        fn into_response(self) -> axum::response::Response {
+            (NOT_FOUND, Json(json!({ "name": "" }))).into_response()
+        }
+    }
+
+    let router = Router::new().route("/:name", get(handler));
+    let send = |app: &Router, uri: &'static str| {
+        app.clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    let lenient = router.clone().layer(ValidateResponseLayer::<User>::new());
+    let strict = router.layer(ValidateResponseLayer::<User>::new().strict(true));
+
+    // Valid responses pass through untouched
+    let res = send(&strict, "/west").await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(
+        json!({ "name": "west" }),
+        serde_json::from_slice::<Value>(&bytes)?
+    );
+
+    // Invalid responses are only logged unless strict
+    let res = send(&lenient, "/empty").await?;
+    assert_eq!(OK, res.status());
+    let res = send(&strict, "/empty").await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+
+    // Error responses aren't validated
+    let res = send(&strict, "/missing").await?;
+    assert_eq!(NOT_FOUND, res.status());
+    Ok(())
+}
+
+#[cfg(feature = "utoipa")]
+#[test]
+fn body_rejections() {
+    use crate::{Body, BodyConfig, BodyError, BodyRejections, BAD_REQUEST};
+    use axum::extract::rejection::JsonRejection;
+    use serde::{Deserialize, Serialize};
+    use utoipa::{IntoResponses, ToSchema};
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Serialize, ToSchema)]
+    struct Problem {
+        message: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Split;
+
+    impl BodyError for Split {
+        type Error = Problem;
+
+        fn json_error(rejection: JsonRejection) -> Problem {
+            Problem {
+                message: rejection.body_text(),
+            }
+        }
+
+        fn validate_error(err: ValidationErrors) -> Problem {
+            Problem {
+                message: err.to_string(),
+            }
+        }
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Shared;
+
+    impl BodyError for Shared {
+        type Error = Problem;
+
+        fn json_error(rejection: JsonRejection) -> Problem {
+            Problem {
+                message: rejection.body_text(),
+            }
+        }
+
+        fn validate_error(err: ValidationErrors) -> Problem {
+            Problem {
+                message: err.to_string(),
+            }
+        }
+
+        fn config() -> BodyConfig {
+            BodyConfig {
+                validate_status: BAD_REQUEST,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    let split = BodyRejections::<Split>::responses();
+    assert_eq!(vec!["400", "422"], split.keys().collect::<Vec<_>>());
+    assert_eq!(split.len(), Body::<Split>::responses().len());
+
+    let shared = Body::<Shared>::responses();
+    assert_eq!(vec!["400"], shared.keys().collect::<Vec<_>>());
+    let utoipa::openapi::RefOr::T(response) = &shared["400"] else {
+        panic!("expected an inline response");
+    };
+    assert_eq!(
+        "Malformed request payload or failed validation",
+        response.description
+    );
+}
+
+#[cfg(feature = "path-errors")]
+#[tokio::test]
+async fn json_path_errors() -> Result<()> {
+    use crate::{Body, DefaultError, BAD_REQUEST, OK};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Item {
+        #[allow(dead_code)]
+        price: u32,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Order {
+        items: Vec<Item>,
+    }
+
+    impl DefaultError for Order {}
+
+    async fn handler(Body(order): Body<Order>) -> String {
+        order.items.len().to_string()
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"items":[{"price":1}]}"#).await?;
+    assert_eq!(OK, res.status());
+
+    let res = send(r#"{"items":[{"price":1},{"price":"free"}]}"#).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["fields"]["items[1].price"][0].is_string(), "{error}");
+
+    // Without a path, e.g. a syntax error at the root, `json_error` is used as usual
+    let res = send("nope").await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["fields"].get("items[1].price").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn body_vec() -> Result<()> {
+    use crate::{BodyVec, DefaultError, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Item {
+        #[validate(range(min = 1))]
+        price: u32,
+    }
+
+    impl DefaultError for Item {}
+
+    async fn handler(BodyVec(items): BodyVec<Item>) -> String {
+        items.iter().map(|item| item.price).sum::<u32>().to_string()
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"[{"price":1},{"price":2}]"#).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"3", &bytes[..]);
+
+    // Every invalid element is reported under its index
+    let res = send(r#"[{"price":0},{"price":2},{"price":0}]"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    let indexes = error.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(vec!["0", "2"], indexes);
+    assert!(error["0"]["fields"]["price"][0].is_string());
+
+    let res = send(r#"{"price":1}"#).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn bodyless_methods() -> Result<()> {
+    use crate::{BodyConfig, DefaultError, EmptyBody, BAD_REQUEST, OK, UNSUPPORTED_MEDIA_TYPE};
+    use axum::{routing::on, routing::MethodFilter, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Filter {
+        #[validate(length(min = 1))]
+        name: Option<String>,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Required {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Strict {
+        #[allow(dead_code)]
+        name: Option<String>,
+    }
+
+    impl DefaultError for Filter {}
+    impl DefaultError for Required {}
+
+    impl DefaultError for Strict {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                empty_body: EmptyBody::Reject,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn filter(crate::Body(filter): crate::Body<Filter>) -> String {
+        filter.name.unwrap_or_default()
+    }
+
+    async fn required(_: crate::Body<Required>) {}
+
+    async fn strict(_: crate::Body<Strict>) {}
+
+    let methods = MethodFilter::GET
+        .or(MethodFilter::HEAD)
+        .or(MethodFilter::OPTIONS)
+        .or(MethodFilter::POST);
+
+    let app = Router::new()
+        .route("/filter", on(methods, filter))
+        .route("/required", on(methods, required))
+        .route("/strict", on(methods, strict));
+
+    for method in ["GET", "HEAD", "OPTIONS"] {
+        let request = |uri| Request::builder().method(method).uri(uri);
+
+        let res = app
+            .clone()
+            .oneshot(request("/filter").body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(OK, res.status(), "{method} /filter");
+
+        let res = app
+            .clone()
+            .oneshot(request("/required").body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(BAD_REQUEST, res.status(), "{method} /required");
+
+        // `EmptyBody::Reject` wins over the `{}` of body-less methods
+        let res = app
+            .clone()
+            .oneshot(request("/strict").body(axum::body::Body::empty())?)
+            .await?;
+        assert_eq!(BAD_REQUEST, res.status(), "{method} /strict");
+
+        // A body is still parsed and validated
+        let res = app
+            .clone()
+            .oneshot(
+                request("/filter")
+                    .header("content-type", "application/json")
+                    .body(axum::body::Body::from(r#"{"name":""}"#))?,
+            )
+            .await?;
+        assert_ne!(OK, res.status(), "{method} /filter with body");
+    }
+
+    // Other methods still need a JSON `Content-Type`
+    let res = app
+        .oneshot(Request::post("/filter").body(axum::body::Body::empty())?)
+        .await?;
+    assert_eq!(UNSUPPORTED_MEDIA_TYPE, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_registry() -> Result<()> {
+    use crate::{static_registry_service, Registry, NOT_FOUND, OK};
+    use axum::{extract::Path, http::StatusCode, routing::get, Router};
+
+    struct Tenant {
+        name: &'static str,
+    }
+
+    async fn handler(
+        Static(tenants): Static<Registry<Tenant>>,
+        Path(key): Path<String>,
+    ) -> Result<&'static str, (StatusCode, &'static str)> {
+        Ok(tenants.lookup(&key)?.name)
+    }
+
+    static WEST: Tenant = Tenant { name: "West" };
+    static EAST: Tenant = Tenant { name: "East" };
+
+    let app = Router::new()
+        .route("/:tenant", get(handler))
+        .layer(static_registry_service!("west" => &WEST, "east" => &EAST));
+    let send = |uri: &'static str| {
+        app.clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    for (uri, name) in [("/west", "West"), ("/east", "East")] {
+        let res = send(uri).await?;
+        assert_eq!(OK, res.status(), "{uri}");
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(name.as_bytes(), &bytes[..]);
+    }
+
+    let res = send("/north").await?;
+    assert_eq!(NOT_FOUND, res.status());
+    Ok(())
+}
+
+#[cfg(feature = "qs")]
+#[tokio::test]
+async fn qs_query_round_trip() -> Result<()> {
+    use crate::{DefaultError, QsQuery, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::get, Json, Router};
+    use serde::{Deserialize, Serialize};
+    use validator::Validate;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+    struct Search {
+        #[validate(nested)]
+        filter: Filter,
+        ids: Vec<u32>,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+    struct Filter {
+        #[serde(default)]
+        #[validate(length(min = 1))]
+        status: Vec<String>,
+        price: Range,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+    struct Range {
+        min: u32,
+        max: u32,
+    }
+
+    impl DefaultError for Search {}
+
+    async fn handler(QsQuery(search): QsQuery<Search>) -> Json<Search> {
+        Json(search)
+    }
+
+    let app = Router::new().route("/", get(handler));
+    let search = Search {
+        filter: Filter {
+            status: vec!["open".into(), "closed".into()],
+            price: Range { min: 10, max: 99 },
+        },
+        ids: vec![1, 2, 3],
+    };
+
+    // Percent-encoded brackets as sent by browsers
+    let query = serde_qs::to_string(&search)?;
+    let res = app
+        .clone()
+        .oneshot(Request::get(f!("/?{}", query)).body(axum::body::Body::empty())?)
+        .await?;
+
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(search, serde_json::from_slice::<Search>(&bytes)?);
+
+    // Literal brackets, nested maps and both array styles
+    let query = "filter[status][]=open&filter[status][]=closed&filter[price][min]=10\
+                 &filter[price][max]=99&ids[0]=1&ids[1]=2&ids[2]=3";
+    let res = app
+        .clone()
+        .oneshot(Request::get(f!("/?{}", query)).body(axum::body::Body::empty())?)
+        .await?;
+
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(search, serde_json::from_slice::<Search>(&bytes)?);
+
+    // Nested values are validated too
+    let query = "filter[price][min]=1&filter[price][max]=2&ids[]=1";
+    let res = app
+        .oneshot(Request::get(f!("/?{}", query)).body(axum::body::Body::empty())?)
+        .await?;
+
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn require_content_type() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, BAD_REQUEST, OK, UNSUPPORTED_MEDIA_TYPE};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Strict {
+        name: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Relaxed {
+        name: String,
+    }
+
+    impl DefaultError for Strict {}
+
+    impl DefaultError for Relaxed {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                require_content_type: false,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn strict(Body(body): Body<Strict>) -> String {
+        body.name
+    }
+
+    async fn relaxed(Body(body): Body<Relaxed>) -> String {
+        body.name
+    }
+
+    let app = Router::new()
+        .route("/strict", post(strict))
+        .route("/relaxed", post(relaxed));
+    let send = |uri: &'static str, content_type: Option<&'static str>| {
+        let mut request = Request::post(uri);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        app.clone().oneshot(
+            request
+                .body(axum::body::Body::from(r#"{"name":"West"}"#))
+                .unwrap(),
+        )
+    };
+
+    let cases = [
+        ("/strict", Some("application/json"), OK),
+        ("/strict", Some("text/json"), UNSUPPORTED_MEDIA_TYPE),
+        ("/strict", None, UNSUPPORTED_MEDIA_TYPE),
+        ("/relaxed", Some("application/json"), OK),
+        ("/relaxed", Some("text/json"), OK),
+        ("/relaxed", None, OK),
+    ];
+    for (uri, content_type, status) in cases {
+        let res = send(uri, content_type).await?;
+        assert_eq!(status, res.status(), "{uri} {content_type:?}");
+    }
+
+    // The body still has to be JSON
+    let res = app
+        .oneshot(Request::post("/relaxed").body(axum::body::Body::from("name=West"))?)
+        .await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn body_size_limit_layer() -> Result<()> {
+    use crate::{BodySizeLimitLayer, OK, PAYLOAD_TOO_LARGE};
+    use axum::{routing::post, Router};
+    use futures_util::stream;
+
+    let app = Router::new()
+        .route("/", post(|body: String| async move { body }))
+        .layer(BodySizeLimitLayer::new(16));
+
+    let chunked = |chunks: Vec<&'static str>| {
+        let chunks = chunks.into_iter().map(Ok::<_, std::io::Error>);
+        axum::body::Body::from_stream(stream::iter(chunks))
+    };
+
+    // Streamed without a `Content-Length`, only the buffered size can catch it
+    let req = Request::post("/").body(chunked(vec!["0123456789", "0123456789"]))?;
+    assert!(req.headers().get("content-length").is_none());
+
+    let res = app.clone().oneshot(req).await?;
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+
+    let res = app
+        .clone()
+        .oneshot(Request::post("/").body(chunked(vec!["0123", "4567"]))?)
+        .await?;
+
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"01234567", &bytes[..]);
+
+    let res = app
+        .oneshot(
+            Request::post("/")
+                .header("content-length", "1024")
+                .body(axum::body::Body::empty())?,
+        )
+        .await?;
+
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_locked() -> Result<()> {
+    use crate::{StaticLocked, INTERNAL_SERVER_ERROR, OK};
+    use axum::{http::StatusCode, routing::post, Router};
+    use std::sync::RwLock;
+
+    async fn handler(counter: StaticLocked<u32>) -> Result<String, (StatusCode, &'static str)> {
+        let mut count = counter.write()?;
+        *count += 1;
+        Ok(count.to_string())
+    }
+
+    static COUNTER: RwLock<u32> = RwLock::new(0);
+    static POISONED: RwLock<u32> = RwLock::new(0);
+
+    let send = |lock: &'static RwLock<u32>| {
+        Router::new()
+            .route("/", post(handler))
+            .layer(static_service!(lock))
+            .oneshot(Request::post("/").body(axum::body::Body::empty()).unwrap())
+    };
+
+    for count in ["1", "2"] {
+        let res = send(&COUNTER).await?;
+        assert_eq!(OK, res.status());
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(count.as_bytes(), &bytes[..]);
+    }
+
+    // Panicking with the write guard held poisons the lock for every later request
+    let _ = std::thread::spawn(|| {
+        let _guard = POISONED.write().unwrap();
+        panic!("poison the lock");
+    })
+    .join();
+
+    let res = send(&POISONED).await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn all_rejections() -> Result<()> {
+    use crate::{All, Body, DefaultError, Query, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct CreateUser {
+        #[validate(length(min = 3))]
+        name: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Options {
+        #[validate(range(max = 10))]
+        retries: u32,
+    }
+
+    impl DefaultError for CreateUser {}
+    impl DefaultError for Options {}
+
+    async fn handler(
+        All((Body(user), Query(options)), _): All<(Body<CreateUser>, Query<Options>)>,
+    ) -> String {
+        f!("{} {}", user.name, options.retries)
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |uri: &'static str, body: &'static str| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send("/?retries=3", r#"{"name":"West"}"#).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"West 3", &bytes[..]);
+
+    // Both extractors fail and are reported together
+    let res = send("/?retries=30", r#"{"name":"W"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["body"]["fields"]["name"].is_array(), "{error}");
+    assert!(error["query"]["fields"]["retries"].is_array(), "{error}");
+
+    // Only the failing one is reported
+    let res = send("/?retries=30", r#"{"name":"West"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error.get("body").is_none());
+    assert!(error["query"]["fields"]["retries"].is_array(), "{error}");
+    Ok(())
+}
+
+#[cfg(feature = "borrow")]
+#[tokio::test]
+async fn body_ref() -> Result<()> {
+    use crate::{BodyRef, DefaultError, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(yoke::Yokeable, Deserialize, Validate)]
+    struct Order<'a> {
+        #[validate(length(min = 1))]
+        customer: &'a str,
+    }
+
+    impl DefaultError for Order<'static> {}
+
+    async fn handler(order: BodyRef<Order<'static>>) -> String {
+        order.get().customer.to_owned()
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"customer":"West"}"#).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"West", &bytes[..]);
+
+    // Parse failures go through `json_error` like `Body<T>`
+    let res = send(r#"{"customer":1}"#).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert!(error["message"].is_string());
+    assert!(error.get("fields").is_none(), "{error}");
+
+    let res = send(r#"{"customer":""}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    Ok(())
+}
+
+#[cfg(feature = "multipart")]
+#[tokio::test]
+async fn multipart_form() -> Result<()> {
+    use crate::{
+        DefaultError, MultipartFiles, MultipartForm, UploadedFile, BAD_REQUEST, OK,
+        PAYLOAD_TOO_LARGE, UNPROCESSABLE_ENTITY,
+    };
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Upload {
+        #[validate(length(min = 1))]
+        title: String,
+        #[serde(skip)]
+        avatar: Option<UploadedFile>,
+    }
+
+    impl DefaultError for Upload {}
+
+    impl MultipartFiles for Upload {
+        fn insert_file(&mut self, field: &str, file: UploadedFile) -> Result<(), UploadedFile> {
+            if field != "avatar" {
+                return Err(file);
+            }
+
+            self.avatar = Some(file);
+            Ok(())
+        }
+
+        fn max_field_bytes() -> Option<usize> {
+            Some(8)
+        }
+    }
+
+    async fn handler(MultipartForm(upload): MultipartForm<Upload>) -> String {
+        let size = upload.avatar.map_or(0, |avatar| avatar.bytes.len());
+        f!("{} {}", upload.title, size)
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |parts: &[(&str, Option<&str>, &str)]| {
+        let mut body = String::new();
+        for (name, file_name, value) in parts {
+            body += "--BOUNDARY\r\n";
+            match file_name {
+                Some(file_name) => {
+                    body += &f!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
+                        name,
+                        file_name
+                    );
+                }
+                None => body += &f!("Content-Disposition: form-data; name=\"{}\"\r\n", name),
+            }
+            body += &f!("\r\n{}\r\n", value);
+        }
+        body += "--BOUNDARY--\r\n";
+
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "multipart/form-data; boundary=BOUNDARY")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(&[("title", None, "West"), ("avatar", Some("a.png"), "1234")]).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"West 4", &bytes[..]);
+
+    let cases = [
+        (vec![("title", None, "")], UNPROCESSABLE_ENTITY),
+        (vec![("title", Some("a.txt"), "West")], BAD_REQUEST),
+        (
+            vec![
+                ("title", None, "West"),
+                ("avatar", Some("a.png"), "123456789"),
+            ],
+            PAYLOAD_TOO_LARGE,
+        ),
+    ];
+    for (parts, status) in cases {
+        let res = send(&parts).await?;
+        assert_eq!(status, res.status(), "{parts:?}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn conditional_requests() -> Result<()> {
+    use crate::{respond_conditional, IfMatch, IfNoneMatch, NOT_MODIFIED, OK};
+    use crate::{NO_CONTENT, PRECONDITION_FAILED};
+    use axum::{http::StatusCode, response::Response, routing::get, Router};
+
+    async fn show(if_none_match: IfNoneMatch) -> Response {
+        respond_conditional(&if_none_match, "v2", "West")
+    }
+
+    async fn update(if_match: IfMatch) -> Result<StatusCode, StatusCode> {
+        if_match.precondition("v2")?;
+        Ok(NO_CONTENT)
+    }
+
+    let app = Router::new().route("/", get(show).put(update));
+    let send = |method: &'static str, header: Option<(&'static str, &'static str)>| {
+        let mut request = Request::builder().method(method).uri("/");
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        app.clone()
+            .oneshot(request.body(axum::body::Body::empty()).unwrap())
+    };
+
+    let res = send("GET", None).await?;
+    assert_eq!(OK, res.status());
+    assert_eq!("\"v2\"", res.headers()["etag"]);
+
+    let cases = [
+        ("GET", Some(("if-none-match", "\"v2\"")), NOT_MODIFIED),
+        (
+            "GET",
+            Some(("if-none-match", "\"v1\", W/\"v2\"")),
+            NOT_MODIFIED,
+        ),
+        ("GET", Some(("if-none-match", "*")), NOT_MODIFIED),
+        ("GET", Some(("if-none-match", "\"v1\"")), OK),
+        // Writes go through without `If-Match` or with the current tag
+        ("PUT", None, NO_CONTENT),
+        ("PUT", Some(("if-match", "\"v2\"")), NO_CONTENT),
+        ("PUT", Some(("if-match", "\"v1\"")), PRECONDITION_FAILED),
+        ("PUT", Some(("if-match", "W/\"v2\"")), PRECONDITION_FAILED),
+    ];
+    for (method, header, status) in cases {
+        let res = send(method, header).await?;
+        assert_eq!(status, res.status(), "{method} {header:?}");
+    }
+    Ok(())
+}
+
+#[test]
+fn rate_limited_retry_after() {
+    use crate::{RateLimited, TOO_MANY_REQUESTS};
+    use axum::response::IntoResponse;
+    use std::time::{Duration, SystemTime};
+
+    let res = RateLimited::after(Duration::from_millis(2500)).into_response();
+    assert_eq!(TOO_MANY_REQUESTS, res.status());
+    assert_eq!("3", res.headers()["retry-after"]);
+
+    let res = RateLimited::after(Duration::from_secs(120)).into_response();
+    assert_eq!("120", res.headers()["retry-after"]);
+
+    let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+    let res = RateLimited::until(date).into_response();
+    assert_eq!(TOO_MANY_REQUESTS, res.status());
+    assert_eq!(
+        "Sun, 06 Nov 1994 08:49:37 GMT",
+        res.headers()["retry-after"]
+    );
+
+    // Out of range dates never panic
+    let date = SystemTime::UNIX_EPOCH - Duration::from_secs(60);
+    let res = RateLimited::until(date).into_response();
+    assert_eq!("0", res.headers()["retry-after"]);
+
+    let date = SystemTime::UNIX_EPOCH + Duration::from_secs(300_000_000_000);
+    let res = RateLimited::until(date).into_response();
+    assert_eq!(
+        "Fri, 31 Dec 9999 23:59:59 GMT",
+        res.headers()["retry-after"]
+    );
+}
+
+#[tokio::test]
+async fn duplicate_keys() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, BAD_REQUEST, OK};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Lenient {
+        role: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Strict {
+        role: String,
+    }
+
+    impl DefaultError for Lenient {}
+
+    impl DefaultError for Strict {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                reject_duplicate_keys: true,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn lenient(Body(body): Body<Lenient>) -> String {
+        body.role
+    }
+
+    async fn strict(Body(body): Body<Strict>) -> String {
+        body.role
+    }
+
+    let app = Router::new()
+        .route("/lenient", post(lenient))
+        .route("/strict", post(strict));
+    let send = |uri: &'static str, body: &'static str| {
+        app.clone().oneshot(
+            Request::post(uri)
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // serde_json keeps the last value by default
+    let body = r#"{"role":"user","role":"admin"}"#;
+    let res = send("/lenient", body).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"admin", &bytes[..]);
+
+    let res = send("/strict", body).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    let message = error["message"].as_str().unwrap_or_default();
+    assert!(message.contains("duplicate key `role`"), "{message}");
+
+    // Nested objects are checked too, the same key in sibling objects is fine
+    let res = send("/strict", r#"{"role":"user","meta":[{"a":1},{"a":2}]}"#).await?;
+    assert_eq!(OK, res.status());
+    let res = send("/strict", r#"{"role":"user","meta":{"a":1,"a":2}}"#).await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn defaulted_query() -> Result<()> {
+    use crate::{DefaultError, DefaultedQuery, QueryDefaults, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::get, Extension, Router};
+    use serde::{Deserialize, Serialize};
+    use validator::Validate;
+
+    #[derive(Serialize, Deserialize, Validate)]
+    struct ListParams {
+        page: u32,
+        #[validate(range(max = 100))]
+        per_page: u32,
+    }
+
+    impl DefaultError for ListParams {}
+
+    async fn handler(DefaultedQuery(params): DefaultedQuery<ListParams>) -> String {
+        f!("{} {}", params.page, params.per_page)
+    }
+
+    let defaults = QueryDefaults::new(&ListParams {
+        page: 1,
+        per_page: 20,
+    });
+    let app = Router::new()
+        .route("/", get(handler))
+        .layer(Extension(defaults));
+    let send = |uri: &'static str| {
+        app.clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    let cases = [
+        ("/", "1 20"),
+        ("/?page=3", "3 20"),
+        ("/?page=3&per_page=50", "3 50"),
+    ];
+    for (uri, expected) in cases {
+        let res = send(uri).await?;
+        assert_eq!(OK, res.status(), "{uri}");
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(expected.as_bytes(), &bytes[..], "{uri}");
+    }
+
+    // Given params are still validated
+    let res = send("/?per_page=500").await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    Ok(())
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn compressed_body() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, OK, PAYLOAD_TOO_LARGE, UNSUPPORTED_MEDIA_TYPE};
+    use axum::{routing::post, Router};
+    use flate2::{write::GzEncoder, Compression};
+    use serde::Deserialize;
+    use std::io::Write;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Note {
+        text: String,
+    }
+
+    impl DefaultError for Note {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                max_bytes: Some(256),
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn handler(Body(note): Body<Note>) -> String {
+        note.text.len().to_string()
+    }
+
+    let gzip = |json: &str| -> Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes())?;
+        Ok(encoder.finish()?)
+    };
+
+    let app = Router::new().route("/", post(handler));
+    let send = |encoding: &'static str, body: Vec<u8>| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .header("content-encoding", encoding)
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send("gzip", gzip(r#"{"text":"West"}"#)?).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"4", &bytes[..]);
+
+    // Small on the wire but over `max_bytes` once decoded
+    let json = f!(r#"{{"text":"{}"}}"#, "a".repeat(4096));
+    let body = gzip(&json)?;
+    assert!(body.len() < 256);
+    let res = send("gzip", body).await?;
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+
+    let res = send("zstd", br#"{"text":"West"}"#.to_vec()).await?;
+    assert_eq!(UNSUPPORTED_MEDIA_TYPE, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn fail_fast() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Signup {
+        #[validate(
+            length(min = 3, message = "too short"),
+            email(message = "not an email")
+        )]
+        name: String,
+        #[validate(range(min = 18, message = "too young"))]
+        age: u32,
+    }
+
+    impl DefaultError for Signup {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                fail_fast: true,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn handler(Body(signup): Body<Signup>) -> String {
+        signup.name
+    }
+
+    let res = Router::new()
+        .route("/", post(handler))
+        .oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(r#"{"name":"W","age":3}"#))?,
+        )
+        .await?;
+
+    // `age` sorts before `name` and only its first error is kept
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(json!({ "age": ["too young"] }), error["fields"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn captured_headers() -> Result<()> {
+    use crate::{
+        Body, BodyConfig, DefaultBodyError, DefaultError, BAD_REQUEST, UNPROCESSABLE_ENTITY,
+    };
+    use axum::{http::HeaderMap, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Comment {
+        #[validate(length(min = 1))]
+        text: String,
+    }
+
+    impl DefaultError for Comment {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                captured_headers: &["x-request-id"],
+                ..BodyConfig::default()
+            }
+        }
+
+        fn with_headers(mut error: DefaultBodyError, headers: &HeaderMap) -> DefaultBodyError {
+            if let Some(id) = headers.get("x-request-id").and_then(|id| id.to_str().ok()) {
+                error.message = f!("{} ({})", error.message, id);
+            }
+            error
+        }
+    }
+
+    async fn handler(Body(comment): Body<Comment>) -> String {
+        comment.text
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .header("x-request-id", "req-42")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // Both JSON and validation rejections see the header, read before the body
+    for (body, status) in [("{", BAD_REQUEST), (r#"{"text":""}"#, UNPROCESSABLE_ENTITY)] {
+        let res = send(body).await?;
+        assert_eq!(status, res.status(), "{body}");
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let error = serde_json::from_slice::<Value>(&bytes)?;
+        let message = error["message"].as_str().unwrap_or_default();
+        assert!(message.ends_with("(req-42)"), "{message}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn redirects() -> Result<()> {
+    use crate::{
+        Redirect, INTERNAL_SERVER_ERROR, PERMANENT_REDIRECT, SEE_OTHER, TEMPORARY_REDIRECT,
+    };
+    use axum::{routing::get, Router};
+
+    let app = Router::new()
+        .route("/old", get(|| async { Redirect::permanent("/new") }))
+        .route("/busy", get(|| async { Redirect::temporary("/mirror") }))
+        .route(
+            "/submit",
+            get(|| async { Redirect::see_other("/done?id=1") }),
+        )
+        .route(
+            "/broken",
+            get(|| async { Redirect::see_other("/line\nbreak") }),
+        );
+    let send = |uri: &'static str| {
+        app.clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    let cases = [
+        ("/old", PERMANENT_REDIRECT, "/new"),
+        ("/busy", TEMPORARY_REDIRECT, "/mirror"),
+        ("/submit", SEE_OTHER, "/done?id=1"),
+    ];
+    for (uri, status, location) in cases {
+        let res = send(uri).await?;
+        assert_eq!(status, res.status(), "{uri}");
+        assert_eq!(location, res.headers()["location"], "{uri}");
+    }
+
+    let res = send("/broken").await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    assert!(res.headers().get("location").is_none());
+    Ok(())
+}
+
+#[test]
+fn validate_json_core() -> Result<()> {
+    use crate::{validate_json, DefaultError, BAD_REQUEST, UNPROCESSABLE_ENTITY};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Ping {
+        #[validate(range(max = 10))]
+        count: u32,
+    }
+
+    impl DefaultError for Ping {}
+
+    let ping = validate_json::<Ping>(br#"{"count":3}"#).map_err(|(status, _)| anyhow!(status))?;
+    assert_eq!(3, ping.count);
+
+    let cases: [(&[u8], _); 2] = [
+        (b"{", BAD_REQUEST),
+        (br#"{"count":30}"#, UNPROCESSABLE_ENTITY),
+    ];
+    for (bytes, status) in cases {
+        let Err((rejected, _)) = validate_json::<Ping>(bytes) else {
+            panic!("{} was accepted", String::from_utf8_lossy(bytes));
+        };
+        assert_eq!(status, rejected);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn rhf_error() -> Result<()> {
+    use crate::{Body, BodyError, RhfError, BAD_REQUEST, UNPROCESSABLE_ENTITY};
+    use axum::{extract::rejection::JsonRejection, routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Address {
+        #[validate(length(equal = 5, message = "5 digits"))]
+        zip: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct SignUp {
+        #[validate(email(message = "not an email"), length(min = 6))]
+        email: String,
+        #[validate(nested)]
+        address: Address,
+    }
+
+    impl BodyError for SignUp {
+        type Error = RhfError;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            RhfError::json(rejection)
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            RhfError::from(err)
+        }
+    }
+
+    async fn handler(Body(signup): Body<SignUp>) -> String {
+        signup.email
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"email":"west","address":{"zip":"1"}}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(
+        json!({ "type": "length", "message": "5 digits" }),
+        error["address.zip"]
+    );
+    // One error per field, like react-hook-form without `criteriaMode: "all"`
+    assert!(error["email"].is_object(), "{error}");
+
+    let res = send("{").await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("json", error["root"]["type"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn utf8_bodies() -> Result<()> {
+    use crate::{Body, DefaultError, BAD_REQUEST, OK};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Note {
+        text: String,
+    }
+
+    impl DefaultError for Note {}
+
+    async fn handler(Body(note): Body<Note>) -> String {
+        note.text
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static [u8]| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // A UTF-8 BOM is stripped
+    let res = send(b"\xEF\xBB\xBF{\"text\":\"West\"}").await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"West", &bytes[..]);
+
+    // Invalid UTF-8 and UTF-16 (with its BOM) fail before parsing
+    let cases: [&[u8]; 2] = [
+        b"{\"text\":\"W\xFFst\"}",
+        b"\xFF\xFE{\x00\"\x00t\x00\"\x00:\x00\"\x00W\x00\"\x00}\x00",
+    ];
+    for body in cases {
+        let res = send(body).await?;
+        assert_eq!(BAD_REQUEST, res.status());
+        let bytes = res.into_body().collect().await?.to_bytes();
+        let error = serde_json::from_slice::<Value>(&bytes)?;
+        let message = error["fields"]["body"][0].as_str().unwrap_or_default();
+        assert!(message.starts_with("Body is not valid UTF-8"), "{error}");
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn negotiate_body() -> Result<()> {
+    use crate::{DefaultError, Negotiate, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Signup {
+        #[validate(length(min = 3))]
+        name: String,
+    }
+
+    impl DefaultError for Signup {}
+
+    async fn handler(Negotiate(crate::Body(signup)): Negotiate<crate::Body<Signup>>) -> String {
+        signup.name
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"name":"west"}"#).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"west", &bytes[..]);
+
+    let res = send(r#"{"name":"w"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert!(error["fields"]["name"].is_array());
+    Ok(())
+}
+
+#[cfg(feature = "timeout")]
+#[tokio::test]
+async fn validate_timeout() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, ValidationTimeout, OK, REQUEST_TIMEOUT};
+    use axum::{routing::post, Extension, Router};
+    use serde::Deserialize;
+    use std::time::Duration;
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize)]
+    struct Report {
+        millis: u64,
+    }
+
+    impl Validate for Report {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            std::thread::sleep(Duration::from_millis(self.millis));
+            Ok(())
+        }
+    }
+
+    impl DefaultError for Report {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                validate_timeout: Some(Duration::from_millis(50)),
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn handler(Body(report): Body<Report>) -> String {
+        report.millis.to_string()
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |app: Router, body: &'static str| {
+        app.oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(app.clone(), r#"{"millis":0}"#).await?;
+    assert_eq!(OK, res.status());
+
+    let res = send(app.clone(), r#"{"millis":500}"#).await?;
+    assert_eq!(REQUEST_TIMEOUT, res.status());
+
+    // The extension overrides `validate_timeout`
+    let extended = app.layer(Extension(ValidationTimeout(Duration::from_secs(5))));
+    let res = send(extended, r#"{"millis":500}"#).await?;
+    assert_eq!(OK, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn accepted() -> Result<()> {
+    use crate::{Accepted, ACCEPTED, INTERNAL_SERVER_ERROR};
+    use axum::{routing::post, Router};
+    use serde_json::{json, Value};
+
+    let app = Router::new()
+        .route("/jobs", post(|| async { Accepted::new("/jobs/7") }))
+        .route(
+            "/reports",
+            post(|| async { Accepted::new("/reports/3").with_body(json!({ "id": 3 })) }),
+        )
+        .route("/broken", post(|| async { Accepted::new("/jobs\n7") }));
+    let send = |uri: &'static str| {
+        app.clone()
+            .oneshot(Request::post(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    let res = send("/jobs").await?;
+    assert_eq!(ACCEPTED, res.status());
+    assert_eq!("/jobs/7", res.headers()["location"]);
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert!(bytes.is_empty());
+
+    let res = send("/reports").await?;
+    assert_eq!(ACCEPTED, res.status());
+    assert_eq!("/reports/3", res.headers()["location"]);
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(json!({ "id": 3 }), serde_json::from_slice::<Value>(&bytes)?);
+
+    let res = send("/broken").await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn pagination() -> Result<()> {
+    use crate::{Paginated, Pagination, PaginationDefaults, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::get, Extension, Router};
+    use serde_json::{json, Value};
+
+    async fn handler(pagination: Pagination) -> Paginated<u64> {
+        let data = (pagination.offset()..).take(2).collect();
+        Paginated::new(data, pagination, 42)
+    }
+
+    let app = Router::new().route("/", get(handler));
+    let send = |app: Router, uri: &'static str| {
+        app.oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    let res = send(app.clone(), "/").await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let expected = json!({ "data": [0, 1], "page": 1, "per_page": 20, "total": 42 });
+    assert_eq!(expected, serde_json::from_slice::<Value>(&bytes)?);
+
+    // Clamped to the maximum of the defaults layer
+    let layered = app
+        .clone()
+        .layer(Extension(PaginationDefaults::new(1, 50, 200)));
+    let res = send(layered, "/?page=3&per_page=500").await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let expected = json!({ "data": [400, 401], "page": 3, "per_page": 200, "total": 42 });
+    assert_eq!(expected, serde_json::from_slice::<Value>(&bytes)?);
+
+    let res = send(app.clone(), "/?per_page=0").await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+
+    let res = send(app, "/?page=first").await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejection_into_response() -> Result<()> {
+    use crate::{Body, BodyError, BAD_REQUEST, UNPROCESSABLE_ENTITY};
+    use axum::{
+        extract::rejection::JsonRejection,
+        http::StatusCode,
+        response::{IntoResponse, Response},
+        routing::post,
+        Router,
+    };
+    use serde::Deserialize;
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Tag {
+        #[validate(length(min = 2))]
+        name: String,
+    }
+
+    impl BodyError for Tag {
+        type Error = String;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            rejection.body_text()
+        }
+
+        fn validate_error(_: ValidationErrors) -> Self::Error {
+            "invalid tag".into()
+        }
+
+        fn into_response(status: StatusCode, error: Self::Error) -> Response {
+            (status, error).into_response()
+        }
+    }
+
+    async fn handler(Body(tag): Body<Tag>) -> String {
+        tag.name
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    for (body, status) in [
+        ("{", BAD_REQUEST),
+        (r#"{"name":"a"}"#, UNPROCESSABLE_ENTITY),
+    ] {
+        let res = send(body).await?;
+        assert_eq!(status, res.status(), "{body}");
+        assert!(res.headers()["content-type"]
+            .to_str()?
+            .starts_with("text/plain"));
+    }
+
+    let res = send(r#"{"name":"a"}"#).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"invalid tag", &bytes[..]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn validation_errors_header() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, BAD_REQUEST, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Item {
+        #[validate(length(min = 1))]
+        sku: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Order {
+        #[validate(length(min = 1))]
+        name: String,
+        #[validate(nested)]
+        items: Vec<Item>,
+    }
+
+    impl DefaultError for Order {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                validation_errors_header: true,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn handler(Body(order): Body<Order>) -> String {
+        order.name
+    }
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"name":"","items":[{"sku":""}]}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    assert_eq!("items.0.sku,name", res.headers()["x-validation-errors"]);
+
+    // Only validation rejections carry it
+    let res = send("{").await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    assert!(res.headers().get("x-validation-errors").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn skip_validation() -> Result<()> {
+    use crate::{Body, DefaultError, SkipValidation, BAD_REQUEST, OK, UNPROCESSABLE_ENTITY};
+    use axum::{routing::post, Extension, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Import {
+        #[validate(length(min = 3))]
+        name: String,
+    }
+
+    impl DefaultError for Import {}
+
+    async fn handler(Body(import): Body<Import>) -> String {
+        import.name
+    }
+
+    let send = |app: Router, body: &'static str| {
+        app.oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let app = Router::new().route("/", post(handler));
+    let trusted = app.clone().layer(Extension(SkipValidation(true)));
+    let untrusted = app.clone().layer(Extension(SkipValidation(false)));
+
+    let res = send(app, r#"{"name":"w"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+
+    let res = send(untrusted, r#"{"name":"w"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+
+    let res = send(trusted.clone(), r#"{"name":"w"}"#).await?;
+    assert_eq!(OK, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"w", &bytes[..]);
+
+    // Still parsed
+    let res = send(trusted, "{").await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn try_extract() -> Result<()> {
+    use crate::{Body, BodyConfig, BodyFailure, DefaultError, PAYLOAD_TOO_LARGE};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Label {
+        #[validate(length(min = 2))]
+        name: String,
+    }
+
+    impl DefaultError for Label {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                max_bytes: Some(32),
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    let request = |body: &'static str| {
+        Request::post("/")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body))
+            .unwrap()
+    };
+
+    let label = Body::<Label>::try_extract(request(r#"{"name":"west"}"#), &()).await;
+    assert_eq!("west", label.map_err(|err| anyhow!("{err:?}"))?.name);
+
+    let failure = Body::<Label>::try_extract(request("{"), &()).await;
+    assert!(matches!(failure, Err(BodyFailure::Json(_))), "{failure:?}");
+
+    let failure = Body::<Label>::try_extract(request(r#"{"name":"w"}"#), &()).await;
+    let err = match failure {
+        Err(BodyFailure::Validation(err)) => err,
+        other => return Err(anyhow!("expected a validation failure, got {other:?}")),
+    };
+    assert!(err.field_errors().contains_key("name"));
+
+    let body = r#"{"name":"a name well over the limit"}"#;
+    let failure = Body::<Label>::try_extract(request(body), &()).await;
+    let res = match failure {
+        Err(BodyFailure::Other(res)) => res,
+        other => return Err(anyhow!("expected a rendered failure, got {other:?}")),
+    };
+    assert_eq!(PAYLOAD_TOO_LARGE, res.status());
+    Ok(())
+}
+
+#[cfg(feature = "jsonschema")]
+#[tokio::test]
+async fn schema_body() -> Result<()> {
+    use crate::{
+        DefaultError, JsonSchema, SchemaBody, StaticLayer, INTERNAL_SERVER_ERROR, OK,
+        UNPROCESSABLE_ENTITY,
+    };
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    #[derive(Deserialize)]
+    struct Product {
+        name: String,
+    }
+
+    impl DefaultError for Product {}
+
+    static SCHEMA: LazyLock<JsonSchema<Product>> = LazyLock::new(|| {
+        let schema = json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": { "name": { "type": "string", "minLength": 2 } }
+        });
+        JsonSchema::new(&schema).unwrap()
+    });
+
+    async fn handler(SchemaBody(product): SchemaBody<Product>) -> String {
+        product.name
+    }
+
+    let send = |app: Router, body: &'static str| {
+        app.oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let app = Router::new().route("/", post(handler));
+    let layered = app.clone().layer(StaticLayer::new(&*SCHEMA));
+
+    let res = send(layered.clone(), r#"{"name":"lamp"}"#).await?;
+    assert_eq!(OK, res.status());
+
+    let res = send(layered, r#"{"name":"l"}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    let message = error["fields"]["body"][0].as_str().unwrap_or_default();
+    assert!(message.starts_with("/name: "), "{message}");
+
+    // Without the schema layer
+    let res = send(app, r#"{"name":"lamp"}"#).await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn static_and_extension() -> Result<()> {
+    use axum::{routing::get, Extension, Router};
+
+    #[derive(Clone)]
+    struct Settings {
+        name: &'static str,
+    }
+
+    static SETTINGS: Settings = Settings { name: "West" };
+
+    async fn handler(
+        Static(settings): Static<Settings>,
+        Extension(reference): Extension<&'static Settings>,
+        Extension(owned): Extension<Settings>,
+    ) -> String {
+        f!("{} {} {}", settings.name, reference.name, owned.name)
+    }
+
+    let res = Router::new()
+        .route("/", get(handler))
+        .layer(crate::static_and_extension_service!(&SETTINGS))
+        .oneshot(Request::get("/").body(axum::body::Body::empty())?)
+        .await?;
+
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"West West West", &bytes[..]);
+    Ok(())
+}
+
+#[cfg(feature = "jsonwebtoken")]
+#[tokio::test]
+async fn bearer() -> Result<()> {
+    use crate::{Bearer, BearerKey, DefaultError, StaticLayer, OK, UNAUTHORIZED};
+    use axum::{routing::get, Router};
+    use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+    use serde::{Deserialize, Serialize};
+    use serde_json::Value;
+    use validator::Validate;
+
+    #[derive(Serialize, Deserialize, Validate)]
+    struct Claims {
+        #[validate(length(min = 1, message = "subject is required"))]
+        sub: String,
+        exp: u64,
+    }
+
+    impl DefaultError for Claims {}
+
+    static KEY: LazyLock<BearerKey<Claims>> = LazyLock::new(|| {
+        BearerKey::new(
+            DecodingKey::from_secret(b"secret"),
+            Validation::new(Algorithm::HS256),
+        )
+    });
+
+    async fn handler(Bearer(claims): Bearer<Claims>) -> String {
+        claims.sub
+    }
+
+    let token = |sub: &str| {
+        let claims = Claims {
+            sub: sub.into(),
+            exp: 4_000_000_000,
+        };
+        let key = EncodingKey::from_secret(b"secret");
+        jsonwebtoken::encode(&Header::default(), &claims, &key).unwrap()
+    };
+
+    let app = Router::new()
+        .route("/", get(handler))
+        .layer(StaticLayer::new(&*KEY));
+    let send = |authorization: Option<String>| {
+        let mut req = Request::get("/");
+        if let Some(authorization) = authorization {
+            req = req.header("authorization", authorization);
+        }
+        app.clone()
+            .oneshot(req.body(axum::body::Body::empty()).unwrap())
+    };
+
+    // The scheme is case-insensitive
+    for scheme in ["Bearer", "bearer", "BEARER"] {
+        let res = send(Some(f!("{} {}", scheme, token("west")))).await?;
+        assert_eq!(OK, res.status(), "{scheme}");
+        let bytes = res.into_body().collect().await?.to_bytes();
+        assert_eq!(b"west", &bytes[..]);
+    }
+
+    let rejected = [
+        None,
+        Some(f!("Basic {}", token("west"))),
+        Some("Bearer not-a-jwt".into()),
+    ];
+    for authorization in rejected {
+        let res = send(authorization.clone()).await?;
+        assert_eq!(UNAUTHORIZED, res.status(), "{authorization:?}");
+    }
+
+    // Claims failing validation get the same status
+    let res = send(Some(f!("Bearer {}", token("")))).await?;
+    assert_eq!(UNAUTHORIZED, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("subject is required", error["fields"]["sub"][0]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn content_type_status() -> Result<()> {
+    use crate::{Body, BodyConfig, DefaultError, BAD_REQUEST, UNSUPPORTED_MEDIA_TYPE};
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::Validate;
+
+    #[derive(Deserialize, Validate)]
+    struct Current {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    #[derive(Deserialize, Validate)]
+    struct Legacy {
+        #[allow(dead_code)]
+        name: String,
+    }
+
+    impl DefaultError for Current {}
+
+    // Clients written against the old 400
+    impl DefaultError for Legacy {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                content_type_status: BAD_REQUEST,
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn current(_: Body<Current>) {}
+
+    async fn legacy(_: Body<Legacy>) {}
+
+    let app = Router::new()
+        .route("/current", post(current))
+        .route("/legacy", post(legacy));
+    let send = |uri: &'static str, content_type: Option<&'static str>| {
+        let mut request = Request::post(uri);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        app.clone().oneshot(
+            request
+                .body(axum::body::Body::from(r#"{"name":"West"}"#))
+                .unwrap(),
+        )
+    };
+
+    for content_type in [None, Some("text/plain")] {
+        let res = send("/current", content_type).await?;
+        assert_eq!(UNSUPPORTED_MEDIA_TYPE, res.status(), "{content_type:?}");
+
+        let res = send("/legacy", content_type).await?;
+        assert_eq!(BAD_REQUEST, res.status(), "{content_type:?}");
+    }
+
+    // Malformed JSON keeps `json_status`
+    let res = app
+        .oneshot(
+            Request::post("/current")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from("{"))?,
+        )
+        .await?;
+    assert_eq!(BAD_REQUEST, res.status());
+    Ok(())
+}
+
+#[tokio::test]
+async fn json_array_stream() -> Result<()> {
+    use crate::{JsonArrayStream, OK};
+    use axum::response::IntoResponse;
+    use futures_util::stream;
+    use serde::{Serialize, Serializer};
+
+    let res = JsonArrayStream(stream::empty::<u32>()).into_response();
+    assert_eq!(OK, res.status());
+    assert_eq!("application/json", res.headers()["content-type"]);
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"[]", &bytes[..]);
+
+    let res = JsonArrayStream(stream::iter([1, 2, 3])).into_response();
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"[1,2,3]", &bytes[..]);
+
+    struct Row(Option<u32>);
+
+    impl Serialize for Row {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self.0 {
+                Some(id) => id.serialize(serializer),
+                None => Err(serde::ser::Error::custom("broken row")),
+            }
+        }
+    }
+
+    // The body errors instead of ending as a valid but truncated array
+    let rows = stream::iter([Row(Some(1)), Row(None), Row(Some(3))]);
+    let res = JsonArrayStream(rows).into_response();
+    let err = res.into_body().collect().await.unwrap_err();
+    assert!(err.to_string().contains("broken row"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_items() -> Result<()> {
+    use crate::{
+        flatten_errors, json_shape, BodyConfig, BodyError, JsonShape, OK, UNPROCESSABLE_ENTITY,
+    };
+    use axum::extract::rejection::JsonRejection;
+    use axum::{routing::post, Router};
+    use serde::Deserialize;
+    use validator::{Validate, ValidationErrors};
+
+    #[derive(Deserialize, Validate)]
+    struct Bulk {
+        #[allow(dead_code)]
+        items: Vec<Item>,
+    }
+
+    #[derive(Deserialize)]
+    struct Item {
+        #[allow(dead_code)]
+        tags: Vec<String>,
+    }
+
+    impl BodyError for Bulk {
+        type Error = serde_json::Value;
+
+        fn json_error(rejection: JsonRejection) -> Self::Error {
+            serde_json::json!({ "message": rejection.body_text() })
+        }
+
+        fn validate_error(err: ValidationErrors) -> Self::Error {
+            serde_json::json!({ "fields": flatten_errors(&err) })
+        }
+
+        fn config() -> BodyConfig {
+            BodyConfig {
+                max_items: Some(2),
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    async fn handler(_: crate::Body<Bulk>) {}
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: &'static str| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    // Nested arrays aren't bounded
+    let res = send(r#"{"items":[{"tags":["a","b","c","d"]},{"tags":[]}]}"#).await?;
+    assert_eq!(OK, res.status());
+
+    // The third item doesn't match `Item`, only the scan can reject it this way
+    let res = send(r#"{"items":[{"tags":[]},{"tags":[]},{"tags":5}]}"#).await?;
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<serde_json::Value>(&bytes)?;
+    assert_eq!(
+        "Arrays may hold at most 2 items",
+        error["fields"]["body"][0]
+    );
+
+    let too_many = |bytes: &[u8], max_items| {
+        matches!(
+            json_shape(bytes, None, Some(max_items)),
+            Some(JsonShape::TooManyItems)
+        )
+    };
+
+    assert!(too_many(b"[1]", 0));
+    assert!(!too_many(b"[ ]", 0));
+    assert!(!too_many(br#"["a,b,c"]"#, 1));
+    assert!(too_many(b"[[1,2,3],[4]]", 1));
+    assert!(!too_many(b"[[1,2,3]]", 1));
+    assert!(!too_many(br#"{"a":{"b":[1,2,3]}}"#, 1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn missing_static_hook() -> Result<()> {
+    use crate::{
+        ExtensionRejection, MissingStaticHook, StaticConfig, BAD_GATEWAY, INTERNAL_SERVER_ERROR,
+        SERVICE_UNAVAILABLE,
+    };
+    use axum::{routing::get, Extension, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Pool;
+
+    static MISSING: AtomicUsize = AtomicUsize::new(0);
+
+    fn on_missing(type_name: &'static str) -> Option<ExtensionRejection> {
+        MISSING.fetch_add(1, Ordering::Relaxed);
+        type_name
+            .ends_with("Pool")
+            .then_some(ExtensionRejection::new(
+                SERVICE_UNAVAILABLE,
+                "Pool is starting",
+                "Static<Pool>",
+            ))
+    }
+
+    async fn data(Static(data): Static<Data>) -> &'static str {
+        data.0
+    }
+
+    async fn pool(_: Static<Pool>) {}
+
+    let app = Router::new()
+        .route("/data", get(data))
+        .route("/pool", get(pool))
+        .layer(Extension(StaticConfig::<Pool>::new(
+            BAD_GATEWAY,
+            "Replaced by the hook",
+        )))
+        .layer(Extension(MissingStaticHook(on_missing)));
+    let send = |uri: &'static str| {
+        app.clone()
+            .oneshot(Request::get(uri).body(axum::body::Body::empty()).unwrap())
+    };
+
+    // The hook's rejection wins over `StaticConfig`
+    let res = send("/pool").await?;
+    assert_eq!(SERVICE_UNAVAILABLE, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    assert_eq!(b"Pool is starting", &bytes[..]);
+
+    // `None` keeps the default rejection, the hook still sees it
+    let res = send("/data").await?;
+    assert_eq!(INTERNAL_SERVER_ERROR, res.status());
+    assert_eq!(2, MISSING.load(Ordering::Relaxed));
+    Ok(())
+}
+
+#[tokio::test]
+async fn validation_response() -> Result<()> {
+    use crate::{BodyConfig, DefaultError, ValidationResponse, BAD_REQUEST, UNPROCESSABLE_ENTITY};
+    use axum::response::IntoResponse;
+    use serde_json::Value;
+    use validator::{Validate, ValidationError};
+
+    #[derive(Validate)]
+    #[validate(schema(function = "distinct"))]
+    struct Rename {
+        #[validate(length(min = 1, message = "name is required"))]
+        name: String,
+        previous: String,
+    }
+
+    fn distinct(rename: &Rename) -> Result<(), ValidationError> {
+        match rename.name == rename.previous {
+            true => Err(ValidationError::new("distinct").with_message("names must differ".into())),
+            false => Ok(()),
+        }
+    }
+
+    impl DefaultError for Rename {
+        fn config() -> BodyConfig {
+            BodyConfig {
+                validate_status: BAD_REQUEST,
+                schema_key: "_schema",
+                ..BodyConfig::default()
+            }
+        }
+    }
+
+    let err = || {
+        Rename {
+            name: "west".into(),
+            previous: "west".into(),
+        }
+        .validate()
+        .unwrap_err()
+    };
+
+    let res = ValidationResponse(err()).into_response();
+    assert_eq!(UNPROCESSABLE_ENTITY, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("names must differ", error["fields"]["__all__"][0]);
+
+    let res = ValidationResponse(err()).for_body::<Rename>();
+    assert_eq!(BAD_REQUEST, res.status());
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!("names must differ", error["fields"]["_schema"][0]);
+    assert!(error["fields"].get("__all__").is_none());
+    Ok(())
+}
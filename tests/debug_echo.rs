@@ -0,0 +1,83 @@
+//! `AXUM_PLUS_DEBUG_ECHO` is read once per process, so the echo is tested in its own binary
+//! instead of next to the unit tests that parse bodies with it unset.
+#![cfg(feature = "debug-echo")]
+
+use anyhow::Result;
+use axum::{http::Request, routing::post, Router};
+use axum_plus::{Body, BodyError};
+use http_body_util::BodyExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std_plus::f;
+use tower::ServiceExt;
+use validator::Validate;
+
+#[derive(Deserialize, Validate)]
+struct Note {
+    #[validate(length(min = 1))]
+    text: String,
+}
+
+#[derive(Serialize)]
+struct Echoed {
+    message: String,
+    body: String,
+}
+
+impl BodyError for Note {
+    type Error = Echoed;
+
+    fn json_error(rejection: axum::extract::rejection::JsonRejection) -> Self::Error {
+        Echoed {
+            message: rejection.body_text(),
+            body: String::new(),
+        }
+    }
+
+    fn validate_error(err: validator::ValidationErrors) -> Self::Error {
+        Echoed {
+            message: err.to_string(),
+            body: String::new(),
+        }
+    }
+
+    fn echo_body(error: Self::Error, body: &str) -> Self::Error {
+        Echoed {
+            body: body.to_owned(),
+            ..error
+        }
+    }
+}
+
+async fn handler(Body(note): Body<Note>) -> String {
+    note.text
+}
+
+#[tokio::test]
+async fn echoes_failed_bodies() -> Result<()> {
+    std::env::set_var("AXUM_PLUS_DEBUG_ECHO", "1");
+
+    let app = Router::new().route("/", post(handler));
+    let send = |body: String| {
+        app.clone().oneshot(
+            Request::post("/")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap(),
+        )
+    };
+
+    let res = send(r#"{"text":"#.to_owned()).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    assert_eq!(r#"{"text":"#, error["body"]);
+
+    // Cut to 4 KiB
+    let res = send(f!(r#"{{"text":{}"#, "1".repeat(8192))).await?;
+    let bytes = res.into_body().collect().await?.to_bytes();
+    let error = serde_json::from_slice::<Value>(&bytes)?;
+    let body = error["body"].as_str().unwrap_or_default();
+    assert_eq!(4096 + "...".len(), body.len());
+    assert!(body.ends_with("..."));
+    Ok(())
+}